@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, read_dir},
+    hint::black_box,
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use syml::parser;
+
+fn generate_table(entries: usize) -> String {
+    let mut src = String::new();
+    for i in 0..entries {
+        src += &format!("\
+key{i}: value{i}
+nested{i}:
+  name: 'item {i}'
+  desc: \"line\\n{i}\"
+  tags: [a, b, {{c: {i}}}]
+  list:
+  - x: 1
+    y: 2
+  - - {i}
+    - ~
+; comment {i}
+");
+    }
+    src
+}
+
+fn generate_list(depth: usize, items: usize) -> String {
+    let mut src = String::new();
+    for i in 0..items {
+        src += &"- ".repeat(depth);
+        src += &format!("item{i}\n");
+        for j in 0..4 {
+            src += &"  ".repeat(depth-1);
+            src += &format!("- 'item {i} {j}'\n");
+        }
+    }
+    src
+}
+
+/// Returns the time of each parse
+fn measure<T, E>(src: &str, f: fn(&str) -> Result<T, E>) -> Duration {
+    let mut runs = 0;
+    let start = Instant::now();
+    while runs < 3 || start.elapsed() < Duration::from_millis(300) {
+        black_box(f(black_box(src)).is_ok());
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn bench(name: &str, src: &str) {
+    let value = parser::value(src);
+    assert!(value.is_ok(), "{name}: {value:?}");
+    assert_eq!(value, parser::fast::value(src), "{name}");
+    let peg = measure(src, parser::value);
+    let fast = measure(src, parser::fast::value);
+    let mib = |time: Duration| {
+        src.len() as f64 / time.as_secs_f64() / (1024.0 * 1024.0)
+    };
+    println!(
+        "{name:<36} {:>10} B  peg {:>9.2} MiB/s  fast {:>9.2} MiB/s  x{:.2}",
+        src.len(),
+        mib(peg),
+        mib(fast),
+        peg.as_secs_f64() / fast.as_secs_f64(),
+    );
+}
+
+fn main() -> io::Result<()> {
+    let dir = PathBuf::from_iter(["tests", "parse_datas"]);
+    let mut files = read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "syml"));
+    files.sort();
+
+    for file in files {
+        let src = fs::read_to_string(&file)?;
+        let name = file.file_name().unwrap().to_string_lossy();
+        bench(&name, &src);
+    }
+    bench("generated table (1k entries)", &generate_table(1000));
+    bench("generated table (20k entries)", &generate_table(20000));
+    bench("generated nested list (depth 16)", &generate_list(16, 2000));
+    bench("long inline list", &format!("[{}]", vec!["abc"; 100000].join(", ")));
+    Ok(())
+}
@@ -28,6 +28,8 @@ syn match symlEscape /\\x\x\{2}/ contained
 syn match symlEscape /\\u\x\{4}/ contained
 syn match symlEscape /\\u{\x\+}/ contained
 syn match symlEscape /\\U\x\{8}/ contained
+syn match symlTag /!\%([!#$%&()\*+./0-9<=>?@A-Z^\_\`a-z|~\\]\|-\%( \)\@!\)[!#$%&()\*+./0-9<=>?@A-Z^\_\`a-z|~'\\\-]*\ze\%([ \t]\+[^ \t;]\|[ \t]*\%(;.*\)\=$\)/
+syn match symlNull /\~\%([!#$%&()\*+./0-9<=>?@A-Z^\_\`a-z|~'\\\-]\)\@!/
 syn match symlArray /- /
 syn region symlInlineArray start=/\[/ end=/]/ contains=TOP,symlArray,symlKey
 syn region symlInlineTable start=/{/ end=/}/ contains=TOP,symlArray
@@ -37,6 +39,8 @@ syn match symlComment /;.*/
 hi def link symlValue String
 hi def link symlEscape SpecialChar
 hi def link symlEscapeErr Error
+hi def link symlTag Type
+hi def link symlNull Constant
 hi def link symlArray Repeat
 hi def link symlInlineArray NONE
 hi def link symlInlineTable NONE
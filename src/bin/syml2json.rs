@@ -1,4 +1,4 @@
-use syml::cli_utils::{read_input, Config};
+use syml::cli_utils::read_input;
 use peg::str::LineCol;
 use std::{process::exit, io::{stdout, Write}};
 
@@ -16,44 +16,12 @@ FILE: source file\n\
 -b:\n\
 \x20   convert bool format string to JSON boolean\n\
 -N:\n\
-\x20   convert null format string to JSON null\n\
+\x20   convert null format string to JSON null,\n\
+\x20   explicit null (`~` or empty value) is always JSON null\n\
 -w:\n\
 \x20   enable weak convert (like enable all convert)\n\
 ";
 
-fn to_json(val: syml::Value, cfg: &Config) -> json::JsonValue {
-    use json::JsonValue as JV;
-    use syml::Value as SV;
-    match val {
-        SV::String(s) => {
-            (cfg.convert_null && s == "null")
-                .then_some(JV::Null)
-                .or_else(|| cfg.convert_boolean
-                    .then(|| s.parse::<bool>()
-                        .ok()
-                        .map(JV::Boolean))
-                    .flatten())
-                .or_else(|| cfg.convert_number
-                    .then(|| s.parse::<f64>()
-                        .ok()
-                        .map(Into::into)
-                        .map(JV::Number))
-                    .flatten())
-                .unwrap_or(JV::String(s))
-        },
-        SV::Array(arr) => {
-            JV::Array(arr.into_iter()
-                .map(|val| to_json(val, cfg))
-                .collect())
-        },
-        SV::Table(table) => {
-            JV::Object(table.into_iter()
-                .map(|(k, v)| (k, to_json(v, cfg)))
-                .collect())
-        },
-    }
-}
-
 fn main() {
     let input = match read_input(HELP) {
         Ok(s) => s,
@@ -71,7 +39,7 @@ fn main() {
             exit(3);
         },
     };
-    let json_val = to_json(val, &input);
+    let json_val = val.to_json_with(&input.json_options());
     let mut out = stdout();
     if input.is_long_output {
         json_val.write_pretty(&mut out, 4).unwrap();
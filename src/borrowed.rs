@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::{parser::{Key, Node}, Tagged, Value};
+
+pub type BorrowedTable<'a> = LinkedHashMap<Cow<'a, str>, BorrowedValue<'a>>;
+
+/// Value node borrowed from the source, parse by [`parser::borrowed_value`]
+///
+/// [`parser::borrowed_value`]: crate::parser::borrowed_value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BorrowedValue<'a> {
+    String(Cow<'a, str>),
+    Array(Vec<Self>),
+    Table(BorrowedTable<'a>),
+    Tagged(Box<BorrowedTagged<'a>>),
+    Null,
+}
+
+/// A tag and the value it is attached to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BorrowedTagged<'a> {
+    pub tag: Cow<'a, str>,
+    pub value: BorrowedValue<'a>,
+}
+
+impl<'a> BorrowedValue<'a> {
+    /// Returns `true` if the value is [`Null`].
+    ///
+    /// [`Null`]: BorrowedValue::Null
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Self>> {
+        if let Self::Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&BorrowedTable<'a>> {
+        if let Self::Table(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_tagged(&self) -> Option<&BorrowedTagged<'a>> {
+        if let Self::Tagged(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Convert into owned [`Value`]
+    pub fn into_owned(self) -> Value {
+        match self {
+            Self::String(s) => s.into_owned().into(),
+            Self::Array(arr) => {
+                arr.into_iter().map(Self::into_owned).collect()
+            },
+            Self::Table(table) => {
+                table.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect()
+            },
+            Self::Tagged(tagged) => {
+                let BorrowedTagged { tag, value } = *tagged;
+                Value::tagged(tag, value.into_owned())
+            },
+            Self::Null => Value::Null,
+        }
+    }
+}
+impl<'a> Node<'a> for BorrowedValue<'a> {
+    fn plain(s: &'a str) -> Self {
+        Self::String(s.into())
+    }
+    fn quoted(s: Cow<'a, str>) -> Self {
+        Self::String(s)
+    }
+    fn array(arr: Vec<Self>) -> Self {
+        Self::Array(arr)
+    }
+    fn table(entries: Vec<(Key<'a>, Self)>) -> Self {
+        let mut table = BorrowedTable::with_capacity(entries.len());
+        for (k, v) in entries {
+            table.entry(k.name).or_insert(v);
+        }
+        Self::Table(table)
+    }
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        Self::Tagged(Box::new(BorrowedTagged { tag: tag.into(), value }))
+    }
+    fn null() -> Self {
+        Self::Null
+    }
+}
+impl<'a> From<BorrowedValue<'a>> for Value {
+    fn from(value: BorrowedValue<'a>) -> Self {
+        value.into_owned()
+    }
+}
+impl<'a> From<&'a Value> for BorrowedValue<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::String(s) => Self::String(s.into()),
+            Value::Array(arr) => Self::Array(arr.iter().map(Into::into).collect()),
+            Value::Table(table) => {
+                Self::Table(table.iter()
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .collect())
+            },
+            Value::Tagged(tagged) => {
+                let Tagged { tag, value } = &**tagged;
+                Self::Tagged(Box::new(BorrowedTagged {
+                    tag: tag.into(),
+                    value: value.into(),
+                }))
+            },
+            Value::Null => Self::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn borrowed_test() {
+        let src = "a: x\n'b': \"y\"\n\"c\\x64\": \"z\\n\"\ne: !t ~";
+        let value = parser::borrowed_value(src).unwrap();
+        let table = value.as_table().unwrap();
+        let keys = table.keys()
+            .map(|k| matches!(k, Cow::Borrowed(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [true, true, false, true]);
+        let values = table.values()
+            .map(|v| matches!(v, BorrowedValue::String(Cow::Borrowed(_))))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [true, true, false, false]);
+        assert_eq!(table["cd"], BorrowedValue::String("z\n".into()));
+        assert_eq!(table["e"].as_tagged().unwrap().value, BorrowedValue::Null);
+    }
+
+    #[test]
+    fn same_as_value_test() {
+        for entry in fs::read_dir("tests/parse_datas").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "syml") {
+                let src = fs::read_to_string(path).unwrap();
+                let value = parser::value(&src).unwrap();
+                let borrowed = parser::borrowed_value(&src).unwrap();
+                // compared as owned, `Table` may be sorted by the backend
+                assert_eq!(BorrowedValue::from(&value).into_owned(), borrowed.clone().into_owned());
+                assert_eq!(borrowed.into_owned(), value);
+            }
+        }
+    }
+}
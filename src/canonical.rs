@@ -0,0 +1,247 @@
+use std::mem;
+
+use crate::{table, SYMLSerialize, Table, Value};
+
+/// Options of [`Value::canonicalize_with`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalOptions {
+    /// Remove table entries of `~`
+    pub remove_nulls: bool,
+    /// Remove all tags
+    pub strip_tags: bool,
+    /// Sort array items by their compact form, for arrays used as sets
+    pub sort_arrays: bool,
+}
+impl CanonicalOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn remove_nulls(mut self, yes: bool) -> Self {
+        self.remove_nulls = yes;
+        self
+    }
+
+    pub fn strip_tags(mut self, yes: bool) -> Self {
+        self.strip_tags = yes;
+        self
+    }
+
+    pub fn sort_arrays(mut self, yes: bool) -> Self {
+        self.sort_arrays = yes;
+        self
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct Fnv(u64);
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+
+    fn write_value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.write(&[0]),
+            Value::String(s) => {
+                self.write(&[1]);
+                self.write_str(s);
+            },
+            Value::Array(arr) => {
+                self.write(&[2]);
+                self.write_len(arr.len());
+                arr.iter().for_each(|item| self.write_value(item));
+            },
+            Value::Table(table) => {
+                self.write(&[3]);
+                self.write_len(table.len());
+                let mut entries = table.iter().collect::<Vec<_>>();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                for (k, v) in entries {
+                    self.write_str(k);
+                    self.write_value(v);
+                }
+            },
+            Value::Tagged(tagged) => {
+                self.write(&[4]);
+                self.write_str(&tagged.tag);
+                self.write_value(&tagged.value);
+            },
+        }
+    }
+}
+
+fn sort_table(table: &mut Table) {
+    let mut entries = mem::take(table).into_iter().collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    table.extend(entries);
+}
+
+impl Value {
+    /// Sort table keys recursively, same as [`canonicalize_with`] default options
+    ///
+    /// [`canonicalize_with`]: Value::canonicalize_with
+    pub fn canonicalize(&mut self) {
+        self.canonicalize_with(&CanonicalOptions::default())
+    }
+
+    /// Sort table keys recursively by byte order, and normalize by options
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{CanonicalOptions, parser};
+    ///
+    /// let mut value = parser::value("b: !t [y, x]\na: {d: ~, c: 1}").unwrap();
+    /// let options = CanonicalOptions::new()
+    ///     .remove_nulls(true)
+    ///     .strip_tags(true)
+    ///     .sort_arrays(true);
+    /// value.canonicalize_with(&options);
+    /// assert_eq!(value.to_string(), "{a:{c:1},b:[x,y]}");
+    /// ```
+    pub fn canonicalize_with(&mut self, options: &CanonicalOptions) {
+        if options.strip_tags {
+            if let Value::Tagged(tagged) = self {
+                *self = mem::replace(&mut tagged.value, Value::Null);
+                return self.canonicalize_with(options);
+            }
+        }
+        match self.untagged_mut() {
+            Value::Array(arr) => {
+                arr.iter_mut().for_each(|item| item.canonicalize_with(options));
+                if options.sort_arrays {
+                    arr.sort_by_cached_key(|item| item.serialize_min_to_string());
+                }
+            },
+            Value::Table(table) => {
+                table.iter_mut().for_each(|(_, v)| v.canonicalize_with(options));
+                if options.remove_nulls {
+                    let nulls = table.iter()
+                        .filter(|(_, v)| v.is_null())
+                        .map(|(k, _)| k.clone())
+                        .collect::<Vec<_>>();
+                    nulls.iter().for_each(|k| { table::remove(table, k); });
+                }
+                sort_table(table);
+            },
+            _ => (),
+        }
+    }
+
+    /// Same as `==`, but the order of table keys is ignored
+    ///
+    /// `==` of table ignores the order too, when the backend is `indexmap` or `btreemap`
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::parser;
+    ///
+    /// let a = parser::value("x: 1\ny: [{a: 1, b: 2}]").unwrap();
+    /// let b = parser::value("y: [{b: 2, a: 1}]\nx: 1").unwrap();
+    /// assert!(a.eq_unordered(&b));
+    /// ```
+    pub fn eq_unordered(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Table(a), Value::Table(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(k, v)| b.get(k).is_some_and(|o| v.eq_unordered(o)))
+            },
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_unordered(b))
+            },
+            (Value::Tagged(a), Value::Tagged(b)) => {
+                a.tag == b.tag && a.value.eq_unordered(&b.value)
+            },
+            _ => self == other,
+        }
+    }
+
+    /// Stable 64-bit content hash, independent of table key order and platform
+    ///
+    /// Values equal by [`eq_unordered`] have the same hash.
+    /// The hash is 64-bit FNV-1a of the following encoding,
+    /// lengths are `u64` little endian, strings are length and UTF-8 bytes:
+    ///
+    /// - `~`: byte 0
+    /// - String: byte 1, string
+    /// - Array: byte 2, length, items
+    /// - Table: byte 3, length, key string and value of entries sorted by key bytes
+    /// - Tagged: byte 4, tag string, value
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let a = parser::value("x: 1\ny: 2").unwrap();
+    /// let b = parser::value("y: 2\nx: 1").unwrap();
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// assert_eq!(Value::Null.content_hash(), 0xaf63bd4c8601b7df);
+    /// ```
+    ///
+    /// [`eq_unordered`]: Value::eq_unordered
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv(FNV_OFFSET);
+        hasher.write_value(self);
+        hasher.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn canonicalize_test() {
+        let tests = [
+            (CanonicalOptions::new(), "{b: 1, a: {d: ~, c: [z, y]}}", "{a: {c: [z, y], d: ~}, b: 1}"),
+            (CanonicalOptions::new().remove_nulls(true), "[{b: ~, a: ~}, ~]", "[{}, ~]"),
+            (CanonicalOptions::new(), "!t {b: !u {y: 1, x: 2}, a: 3}", "!t {a: 3, b: !u {x: 2, y: 1}}"),
+            (CanonicalOptions::new().strip_tags(true), "!t !u {b: !v [!w x], a: ~}", "{a: ~, b: [x]}"),
+            (CanonicalOptions::new().sort_arrays(true), "[[b, a], {y: 1, x: 2}, c, ~]", "[[a, b], c, {x: 2, y: 1}, ~]"),
+            (CanonicalOptions::new().remove_nulls(true).strip_tags(true), "{a: !t ~}", "{}"),
+        ];
+        for (options, src, expected) in tests {
+            let mut value = parser::value(src).unwrap();
+            value.canonicalize_with(&options);
+            let expected = parser::value(expected).unwrap();
+            assert_eq!(value.to_string(), expected.to_string(), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn hash_test() {
+        let tests = [
+            ("a: [1, {x: 1, y: 2}]\nb: !t c", "b: !t c\na: [1, {y: 2, x: 1}]", true),
+            ("[1, 2]", "[2, 1]", false),
+            ("a: !t 1", "a: 1", false),
+            ("a: ~", "{}", false),
+            ("[a, b]", "['a', 'b']", true),
+            ("[ab]", "[a, b]", false),
+            ("{a: b}", "[a, b]", false),
+        ];
+        for (a, b, eq) in tests {
+            let (a, b) = (parser::value(a).unwrap(), parser::value(b).unwrap());
+            assert_eq!(a.eq_unordered(&b), eq, "{a:?} {b:?}");
+            assert_eq!(a.content_hash() == b.content_hash(), eq, "{a:?} {b:?}");
+        }
+        let value = parser::value("b: [x]\na: !t ~").unwrap();
+        let mut sorted = value.clone();
+        sorted.canonicalize();
+        assert_eq!(sorted.content_hash(), value.content_hash());
+        assert_eq!(value.content_hash(), 0xef67_7cf3_b6d5_e597);
+    }
+}
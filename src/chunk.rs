@@ -0,0 +1,105 @@
+//! Split the source into top-level entries by lines
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::parser;
+
+/// Make the error location of chunk relative to the whole source
+///
+/// `line` is the line number of chunk start
+pub(crate) fn shift_error(
+    mut e: ParseError<LineCol>,
+    offset: usize,
+    line: usize,
+) -> ParseError<LineCol> {
+    e.location.offset += offset;
+    e.location.line += line - 1;
+    e
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DocKind {
+    /// Top-level is block table, each entry is a chunk
+    Table,
+    /// Top-level is block list, each item is a chunk
+    List,
+    /// Top-level is a inline value
+    Single,
+}
+
+/// Returns `true` if the line ends in a `"..."` string continuation
+fn scan_line(line: &str, mut in_string: bool) -> bool {
+    let mut chars = line.chars().peekable();
+    let mut prev_literal = false;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            match ch {
+                '\\' => match chars.peek() {
+                    None | Some(';' | '\r' | '\n') => return true,
+                    Some(_) => { chars.next(); },
+                },
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match ch {
+            ';' => return false,
+            '"' => in_string = true,
+            '\'' if !prev_literal => {
+                chars.by_ref().find(|&ch| ch == '\'');
+                continue;
+            },
+            _ => (),
+        }
+        let mut buf = [0; 4];
+        prev_literal = parser::simple_literal_continue(ch.encode_utf8(&mut buf))
+            .is_ok();
+    }
+    false
+}
+
+/// Line is empty or only comment
+pub(crate) fn is_blank(line: &str) -> bool {
+    let line = line.trim_start_matches([' ', '\t']);
+    line.is_empty() || line.starts_with([';', '\r', '\n'])
+}
+
+/// Find the first line of each top-level entry
+///
+/// Blank and comment lines are belong to the previous entry.
+#[derive(Debug, Default)]
+pub(crate) struct Chunker {
+    /// Guess by the first line, can be changed by the user
+    pub kind: Option<DocKind>,
+    in_string: bool,
+    has_content: bool,
+}
+impl Chunker {
+    fn is_chunk_start(&self, line: &str) -> bool {
+        if line.starts_with([' ', '\t']) || is_blank(line) {
+            return false;
+        }
+        match self.kind {
+            Some(DocKind::List) => line.starts_with("- "),
+            Some(DocKind::Table | DocKind::Single) => !line.starts_with("- "),
+            None => false,
+        }
+    }
+
+    /// Feed the next line, returns `true` if the line starts a new chunk
+    pub fn feed(&mut self, line: &str) -> bool {
+        if self.kind.is_none() && !is_blank(line) {
+            self.kind = Some(if line.starts_with("- ") {
+                DocKind::List
+            } else {
+                DocKind::Table
+            });
+        }
+        let start = !self.in_string
+            && self.is_chunk_start(line)
+            && std::mem::replace(&mut self.has_content, true);
+        self.in_string = scan_line(line, self.in_string);
+        start
+    }
+}
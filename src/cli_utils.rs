@@ -49,6 +49,15 @@ pub struct Config {
     pub convert_boolean: bool,
     pub convert_null: bool,
 }
+impl Config {
+    /// Coercions of `-n`, `-b`, `-N` and `-w`
+    pub fn json_options(&self) -> crate::JsonOptions {
+        crate::JsonOptions::new()
+            .number(self.convert_number)
+            .boolean(self.convert_boolean)
+            .null(self.convert_null)
+    }
+}
 
 pub fn read_input(help: &str) -> io::Result<Config> {
     let mut config = Config::default();
@@ -86,7 +95,7 @@ pub fn read_input(help: &str) -> io::Result<Config> {
             Some(arg) => {
                 let mut stderr = stderr().lock();
                 || -> Result<(), io::Error> {
-                    stderr.write(b"Error: Extra arg: ")?;
+                    stderr.write_all(b"Error: Extra arg: ")?;
                     stderr.write_all(arg.as_encoded_bytes())?;
                     writeln!(stderr)?;
                     Ok(())
@@ -0,0 +1,471 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    hash::BuildHasher,
+    str::FromStr,
+};
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::{AsPath, Kind, Path, PathError, Segment, Value};
+
+/// What failed in [`FromSymlError`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromSymlErrorKind {
+    /// Value is missing or `~`
+    Missing,
+    /// Field of a table is missing, the path is the table
+    MissingField(String),
+    Mismatch {
+        expected: Kind,
+        found: Kind,
+    },
+    /// Mismatch that expects something other than a single kind,
+    /// e.g `string or tagged value`
+    Expected {
+        expected: String,
+        found: Kind,
+    },
+    /// A string failed to parse as type `ty`
+    Parse {
+        ty: &'static str,
+        input: String,
+        message: String,
+    },
+    InvalidPath(PathError),
+    Custom(String),
+}
+
+/// Conversion failed, [`path`] is the location of the failed node
+///
+/// [`path`]: FromSymlError::path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromSymlError {
+    pub path: Path,
+    pub kind: FromSymlErrorKind,
+}
+impl FromSymlError {
+    pub fn new(message: impl Into<String>) -> Self {
+        FromSymlErrorKind::Custom(message.into()).into()
+    }
+
+    /// Type mismatch error, e.g `expected table, found array`
+    pub fn expected(expected: &str, found: &Value) -> Self {
+        FromSymlErrorKind::Expected { expected: expected.into(), found: found.kind() }.into()
+    }
+
+    pub fn missing_field(key: &str) -> Self {
+        FromSymlErrorKind::MissingField(key.into()).into()
+    }
+
+    pub fn missing() -> Self {
+        FromSymlErrorKind::Missing.into()
+    }
+
+    pub fn mismatch(expected: Kind, found: &Value) -> Self {
+        FromSymlErrorKind::Mismatch { expected, found: found.kind() }.into()
+    }
+
+    pub fn parse(ty: &'static str, input: &str, message: impl fmt::Display) -> Self {
+        FromSymlErrorKind::Parse {
+            ty,
+            input: input.to_owned(),
+            message: message.to_string(),
+        }.into()
+    }
+
+    /// Prepend a segment to the path, used by the parent node
+    pub fn at(mut self, segment: impl Into<Segment>) -> Self {
+        self.path.push_front(segment);
+        self
+    }
+
+    /// Prepend a path
+    fn within(mut self, path: &Path) -> Self {
+        for segment in path.segments().iter().rev() {
+            self.path.push_front(segment.clone());
+        }
+        self
+    }
+}
+impl fmt::Display for FromSymlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            FromSymlErrorKind::Missing => f.write_str("missing value"),
+            FromSymlErrorKind::MissingField(key) => write!(f, "missing field `{key}`"),
+            FromSymlErrorKind::Mismatch { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            },
+            FromSymlErrorKind::Expected { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            },
+            FromSymlErrorKind::Parse { ty, input, message } => {
+                write!(f, "invalid {ty} `{input}`: {message}")
+            },
+            FromSymlErrorKind::InvalidPath(e) => e.fmt(f),
+            FromSymlErrorKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+impl Error for FromSymlError { }
+impl From<FromSymlErrorKind> for FromSymlError {
+    fn from(kind: FromSymlErrorKind) -> Self {
+        Self { path: Path::new(), kind }
+    }
+}
+
+/// Convert from [`Value`] without serde
+///
+/// Scalars skip tags and parse strings by [`FromStr`],
+/// use `#[derive(FromSyml)]` of `syml-macros` for structs and enums.
+///
+/// # Examples
+/// ```
+/// use syml::{FromSyml, parser};
+///
+/// let value = parser::value("ports: [80, x]").unwrap();
+/// let err = <Vec<u16>>::from_syml(&value["ports"]).unwrap_err();
+/// assert_eq!(err.to_string(), "[1]: invalid u16 `x`: invalid digit found in string");
+/// ```
+pub trait FromSyml: Sized {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError>;
+
+    /// Value of a missing node, [`None`] is a missing value error
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Convert into [`Value`] without serde
+///
+/// Scalars are strings by [`ToString`],
+/// use `#[derive(ToSyml)]` of `syml-macros` for structs and enums.
+pub trait ToSyml {
+    fn to_syml(&self) -> Value;
+}
+
+fn parse<T>(value: &Value, name: &'static str) -> Result<T, FromSymlError>
+where T: FromStr,
+      T::Err: fmt::Display,
+{
+    let s = value.untagged().as_str()
+        .ok_or_else(|| FromSymlError::mismatch(Kind::String, value))?;
+    s.parse().map_err(|e| FromSymlError::parse(name, s, e))
+}
+
+macro_rules! impl_scalar {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl FromSyml for $ty {
+                fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+                    parse(value, stringify!($ty))
+                }
+            }
+            impl ToSyml for $ty {
+                fn to_syml(&self) -> Value {
+                    Value::String(self.to_string())
+                }
+            }
+        )+
+    };
+}
+impl_scalar! {
+    bool, char, f32, f64,
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+}
+
+impl FromSyml for String {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        value.untagged().as_string()
+            .cloned()
+            .ok_or_else(|| FromSymlError::mismatch(Kind::String, value))
+    }
+}
+impl ToSyml for String {
+    fn to_syml(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+impl ToSyml for str {
+    fn to_syml(&self) -> Value {
+        self.into()
+    }
+}
+
+impl FromSyml for Value {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        Ok(value.clone())
+    }
+}
+impl ToSyml for Value {
+    fn to_syml(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl<T: FromSyml> FromSyml for Option<T> {
+    /// [`Value::Null`] is [`None`]
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        if value.untagged().is_null() {
+            return Ok(None);
+        }
+        T::from_syml(value).map(Some)
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+impl<T: ToSyml> ToSyml for Option<T> {
+    fn to_syml(&self) -> Value {
+        self.as_ref().map_or(Value::Null, T::to_syml)
+    }
+}
+
+impl<T: FromSyml> FromSyml for Box<T> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        T::from_syml(value).map(Box::new)
+    }
+
+    fn from_missing() -> Option<Self> {
+        T::from_missing().map(Box::new)
+    }
+}
+impl<T: ToSyml + ?Sized> ToSyml for Box<T> {
+    fn to_syml(&self) -> Value {
+        (**self).to_syml()
+    }
+}
+impl<T: ToSyml + ?Sized> ToSyml for &T {
+    fn to_syml(&self) -> Value {
+        (**self).to_syml()
+    }
+}
+
+impl<T: FromSyml> FromSyml for Vec<T> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        let arr = value.untagged().as_array()
+            .ok_or_else(|| FromSymlError::mismatch(Kind::Array, value))?;
+        arr.iter().enumerate()
+            .map(|(i, item)| T::from_syml(item).map_err(|e| e.at(i)))
+            .collect()
+    }
+}
+impl<T: ToSyml> ToSyml for [T] {
+    fn to_syml(&self) -> Value {
+        Value::Array(self.iter().map(T::to_syml).collect())
+    }
+}
+impl<T: ToSyml> ToSyml for Vec<T> {
+    fn to_syml(&self) -> Value {
+        self[..].to_syml()
+    }
+}
+
+fn from_table<T, M>(value: &Value) -> Result<M, FromSymlError>
+where T: FromSyml,
+      M: FromIterator<(String, T)>,
+{
+    let table = value.untagged().as_table()
+        .ok_or_else(|| FromSymlError::mismatch(Kind::Table, value))?;
+    table.iter()
+        .map(|(k, v)| Ok((k.clone(), T::from_syml(v).map_err(|e| e.at(k.as_str()))?)))
+        .collect()
+}
+
+fn to_table<'a, T: ToSyml + 'a>(iter: impl Iterator<Item = (&'a String, &'a T)>) -> Value {
+    Value::Table(iter.map(|(k, v)| (k.clone(), v.to_syml())).collect())
+}
+
+impl<T: FromSyml> FromSyml for LinkedHashMap<String, T> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        from_table(value)
+    }
+}
+impl<T: ToSyml> ToSyml for LinkedHashMap<String, T> {
+    fn to_syml(&self) -> Value {
+        to_table(self.iter())
+    }
+}
+impl<T: FromSyml, S: BuildHasher + Default> FromSyml for HashMap<String, T, S> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        from_table(value)
+    }
+}
+impl<T: ToSyml, S> ToSyml for HashMap<String, T, S> {
+    fn to_syml(&self) -> Value {
+        to_table(self.iter())
+    }
+}
+#[cfg(feature = "indexmap")]
+impl<T: FromSyml, S: BuildHasher + Default> FromSyml for indexmap::IndexMap<String, T, S> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        from_table(value)
+    }
+}
+#[cfg(feature = "indexmap")]
+impl<T: ToSyml, S> ToSyml for indexmap::IndexMap<String, T, S> {
+    fn to_syml(&self) -> Value {
+        to_table(self.iter())
+    }
+}
+impl<T: FromSyml> FromSyml for BTreeMap<String, T> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        from_table(value)
+    }
+}
+impl<T: ToSyml> ToSyml for BTreeMap<String, T> {
+    fn to_syml(&self) -> Value {
+        to_table(self.iter())
+    }
+}
+
+impl Value {
+    /// Convert the node at path, [`None`] if it is missing or `~`
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{FromSymlErrorKind, Kind, parser};
+    ///
+    /// let value = parser::value("db: {port: x, hosts: [a]}").unwrap();
+    /// assert_eq!(value.get_as::<String>("db.hosts[0]"), Ok(Some("a".into())));
+    /// assert_eq!(value.get_as::<String>("db.user"), Ok(None));
+    ///
+    /// let err = value.get_as::<u16>("db.port").unwrap_err();
+    /// assert_eq!(err.to_string(), "db.port: invalid u16 `x`: invalid digit found in string");
+    /// let err = value.get_as::<u16>("/db/hosts").unwrap_err();
+    /// assert_eq!(err.kind, FromSymlErrorKind::Mismatch {
+    ///     expected: Kind::String,
+    ///     found: Kind::Array,
+    /// });
+    /// ```
+    pub fn get_as<T: FromSyml>(&self, path: impl AsPath) -> Result<Option<T>, FromSymlError> {
+        let path = path.as_path()
+            .map_err(FromSymlErrorKind::InvalidPath)?;
+        match self.pointer(&path) {
+            Some(value) if !value.untagged().is_null() => {
+                T::from_syml(value).map(Some).map_err(|e| e.within(&path))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Convert the node at path, use [`FromSyml::from_missing`] if it is missing or `~`
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::parser;
+    ///
+    /// let value = parser::value("a: [1]\nb: ~").unwrap();
+    /// assert_eq!(value.require::<Vec<u8>>("a"), Ok(vec![1]));
+    /// assert_eq!(value.require::<Option<u8>>("b"), Ok(None));
+    /// assert_eq!(value.require::<u8>("b").unwrap_err().to_string(), "b: missing value");
+    /// ```
+    pub fn require<T: FromSyml>(&self, path: impl AsPath) -> Result<T, FromSymlError> {
+        self.get_or_else(path, || T::from_missing().ok_or_else(FromSymlError::missing))
+    }
+
+    /// Convert the node at path, `default` if it is missing or `~`
+    pub fn get_or<T: FromSyml>(&self, path: impl AsPath, default: T) -> Result<T, FromSymlError> {
+        self.get_or_else(path, || Ok(default))
+    }
+
+    /// Convert the node at path, [`Default`] if it is missing or `~`
+    pub fn get_or_default<T>(&self, path: impl AsPath) -> Result<T, FromSymlError>
+    where T: FromSyml + Default,
+    {
+        self.get_or_else(path, || Ok(T::default()))
+    }
+
+    fn get_or_else<T: FromSyml>(
+        &self,
+        path: impl AsPath,
+        default: impl FnOnce() -> Result<T, FromSymlError>,
+    ) -> Result<T, FromSymlError> {
+        let path = path.as_path()
+            .map_err(FromSymlErrorKind::InvalidPath)?;
+        match self.get_as(&*path)? {
+            Some(value) => Ok(value),
+            None => default().map_err(|e| e.within(&path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    fn value(src: &str) -> Value {
+        parser::value(src).unwrap()
+    }
+
+    #[test]
+    fn from_syml_test() {
+        assert_eq!(u8::from_syml(&value("!int 5")), Ok(5));
+        assert_eq!(bool::from_syml(&value("true")), Ok(true));
+        assert_eq!(<Option<String>>::from_syml(&value("~")), Ok(None));
+        assert_eq!(<Option<String>>::from_missing(), Some(None));
+        assert_eq!(<Vec<f64>>::from_syml(&value("[1, 2.5]")), Ok(vec![1.0, 2.5]));
+        let map = <BTreeMap<String, Vec<i32>>>::from_syml(&value("b: [1]\na: []")).unwrap();
+        assert_eq!(map, BTreeMap::from([("a".into(), vec![]), ("b".into(), vec![1])]));
+
+        let tests = [
+            ("a: [x]", "expected array, found table"),
+            ("[[1], [2, x]]", "[1][1]: invalid i32 `x`: invalid digit found in string"),
+            ("[[1], {a: 1}]", "[1]: expected array, found table"),
+            ("[[1], ~]", "[1]: expected array, found null"),
+        ];
+        for (src, message) in tests {
+            let err = <Vec<Vec<i32>>>::from_syml(&value(src)).unwrap_err();
+            assert_eq!(err.to_string(), message, "{src:?}");
+        }
+        let err = <HashMap<String, u8>>::from_syml(&value("'a b': 256")).unwrap_err();
+        assert_eq!(err.path, Path::from_iter(["a b"]));
+        assert_eq!(err.to_string(), "\"a b\": invalid u8 `256`: number too large to fit in target type");
+    }
+
+    #[test]
+    fn to_syml_test() {
+        let map = BTreeMap::from([("b", vec![Some(1.5)]), ("a", vec![None])])
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(map.to_syml(), value("a: [~]\nb: [1.5]"));
+        assert_eq!("x".to_syml(), value("x"));
+        assert_eq!(true.to_syml(), value("true"));
+        assert_eq!(Box::new(-3i8).to_syml(), value("-3"));
+    }
+
+    #[test]
+    fn accessor_test() {
+        let value = value("db:\n  port: 80\n  user: ~\n  hosts: [a, !t b]");
+        assert_eq!(value.get_as::<u16>("db.port"), Ok(Some(80)));
+        assert_eq!(value.get_as::<u16>("db.user"), Ok(None));
+        assert_eq!(value.get_or("/db/port", 1u16), Ok(80));
+        assert_eq!(value.get_or("db.timeout", 1u16), Ok(1));
+        assert_eq!(value.get_or_default::<Vec<String>>("db.hosts"), Ok(vec!["a".into(), "b".into()]));
+        assert_eq!(value.get_or_default::<Vec<String>>("db.x"), Ok(vec![]));
+        assert_eq!(value.require::<String>(&Path::from_iter(["db", "hosts"]).join(1)), Ok("b".into()));
+
+        let err = value.require::<u16>("db.hosts[2]").unwrap_err();
+        assert_eq!(err.path.to_string(), "db.hosts[2]");
+        assert_eq!(err.kind, FromSymlErrorKind::Missing);
+        let err = value.get_or::<u16>("db.hosts[0]", 1).unwrap_err();
+        assert_eq!(err.kind, FromSymlErrorKind::Parse {
+            ty: "u16",
+            input: "a".into(),
+            message: "invalid digit found in string".into(),
+        });
+        let err = value.get_as::<Vec<u8>>("db").unwrap_err();
+        assert_eq!(err.to_string(), "db: expected array, found table");
+        let err = value.get_as::<u8>("db[").unwrap_err();
+        assert!(matches!(err.kind, FromSymlErrorKind::InvalidPath(_)), "{err:?}");
+        assert!(err.path.is_empty());
+    }
+}
@@ -0,0 +1,495 @@
+use std::{error::Error, fmt::{self, Arguments}};
+
+use crate::{Path, SYMLSerialize, Segment, Table, Value};
+
+/// A change of [`Patch`]
+///
+/// `old` values are kept for review and audit, they are not checked on apply.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Insert into table or array, replace the existing table value
+    Add { path: Path, value: Value },
+    Remove { path: Path, old: Value },
+    Replace { path: Path, old: Value, value: Value },
+    /// Remove and add the value, on the same table key move it to the end
+    Move { from: Path, path: Path },
+}
+impl Operation {
+    pub fn path(&self) -> &Path {
+        match self {
+            Operation::Add { path, .. }
+            | Operation::Remove { path, .. }
+            | Operation::Replace { path, .. }
+            | Operation::Move { path, .. } => path,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Add { .. } => "add",
+            Operation::Remove { .. } => "remove",
+            Operation::Replace { .. } => "replace",
+            Operation::Move { .. } => "move",
+        }
+    }
+
+    fn apply(&self, root: &mut Value) -> Result<(), &'static str> {
+        match self {
+            Operation::Add { path, value } => add(root, path, value.clone()),
+            Operation::Remove { path, .. } => {
+                if path.is_empty() {
+                    return Err("cannot remove root");
+                }
+                root.remove_at(path).ok_or("path not found")?;
+                Ok(())
+            },
+            Operation::Replace { path, value, .. } => {
+                *root.pointer_mut(path).ok_or("path not found")? = value.clone();
+                Ok(())
+            },
+            Operation::Move { from, path } => {
+                if path.segments().starts_with(from.segments()) && path != from {
+                    return Err("cannot move into itself");
+                }
+                if from.is_empty() {
+                    return Err("cannot move root");
+                }
+                let value = root.remove_at(from).ok_or("path not found")?;
+                add(root, path, value)
+            },
+        }
+    }
+
+    fn to_value(&self, with_old: bool) -> Value {
+        let mut table = Table::new();
+        table.insert("op".into(), self.name().into());
+        if let Operation::Move { from, .. } = self {
+            table.insert("from".into(), from.to_pointer().into());
+        }
+        table.insert("path".into(), self.path().to_pointer().into());
+        if let Operation::Add { value, .. } | Operation::Replace { value, .. } = self {
+            table.insert("value".into(), value.clone());
+        }
+        if let (Operation::Remove { old, .. } | Operation::Replace { old, .. }, true)
+            = (self, with_old)
+        {
+            table.insert("old".into(), old.clone());
+        }
+        table.into()
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let table = value.as_table().ok_or("expected table")?;
+        let field = |name: &str| {
+            table.get(name).ok_or_else(|| format!("missing `{name}`"))
+        };
+        let path = |name: &str| {
+            let s = field(name)?.as_str()
+                .ok_or_else(|| format!("expected string `{name}`"))?;
+            Path::parse_pointer(s).map_err(|e| format!("`{name}` {e}"))
+        };
+        let old = || table.get("old").cloned().unwrap_or(Value::Null);
+        let op = field("op")?.as_str().ok_or("expected string `op`")?;
+        Ok(match op {
+            "add" => Operation::Add { path: path("path")?, value: field("value")?.clone() },
+            "remove" => Operation::Remove { path: path("path")?, old: old() },
+            "replace" => Operation::Replace {
+                path: path("path")?,
+                old: old(),
+                value: field("value")?.clone(),
+            },
+            "move" => Operation::Move { from: path("from")?, path: path("path")? },
+            _ => return Err(format!("unknown op `{op}`")),
+        })
+    }
+}
+
+/// Add value into the existing parent of path
+fn add(root: &mut Value, path: &Path, value: Value) -> Result<(), &'static str> {
+    let Some((last, parent)) = path.segments().split_last() else {
+        *root = value;
+        return Ok(());
+    };
+    let parent = root.pointer_mut(&parent.to_vec().into())
+        .ok_or("parent not found")?;
+    match (parent.untagged_mut(), last) {
+        (Value::Table(table), Segment::Key(k)) => {
+            table.insert(k.clone(), value);
+        },
+        (Value::Array(arr), seg) => {
+            let i = match seg {
+                Segment::Index(i) => *i,
+                Segment::Key(k) if k == "-" => arr.len(),
+                Segment::Key(k) => k.parse().map_err(|_| "invalid array index")?,
+            };
+            if i > arr.len() {
+                return Err("array index out of range");
+            }
+            arr.insert(i, value);
+        },
+        _ => return Err("parent is not table or array"),
+    }
+    Ok(())
+}
+
+/// Failed to apply or load a patch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    /// Index of the failed operation
+    pub index: usize,
+    pub message: String,
+}
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "patch operation {}: {}", self.index, self.message)
+    }
+}
+impl Error for PatchError { }
+
+/// Operations from a value to another, created by [`diff`]
+///
+/// # Examples
+/// ```
+/// use syml::{diff, parser, Patch};
+///
+/// let old = parser::value("a: 1\nb: [x, y]\nc: z").unwrap();
+/// let new = parser::value("a: 2\nb: [x]\nd: z").unwrap();
+/// let patch = diff(&old, &new);
+/// assert_eq!(patch.to_json_patch(), parser::value(concat!(
+///     "[{op: replace, path: /a, value: 2},",
+///     " {op: remove, path: /b/1},",
+///     " {op: move, from: /c, path: /d}]",
+/// )).unwrap());
+///
+/// let mut value = old.clone();
+/// patch.apply(&mut value).unwrap();
+/// assert_eq!(value, new);
+///
+/// let loaded = Patch::from_value(&patch.to_value()).unwrap();
+/// let mut value = old.clone();
+/// loaded.apply(&mut value).unwrap();
+/// assert_eq!(value, new);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Patch {
+    ops: Vec<Operation>,
+}
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.ops
+    }
+
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.ops
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub fn push(&mut self, op: Operation) {
+        self.ops.push(op);
+    }
+
+    /// Apply operations in order, value is unchanged on error
+    pub fn apply(&self, value: &mut Value) -> Result<(), PatchError> {
+        let mut patched = value.clone();
+        for (index, op) in self.ops.iter().enumerate() {
+            op.apply(&mut patched).map_err(|message| PatchError {
+                index,
+                message: format!("{message}: {}", op.path().to_pointer()),
+            })?;
+        }
+        *value = patched;
+        Ok(())
+    }
+
+    /// Array of operation tables, paths are JSON Pointer,
+    /// `old` values are kept in `old` field
+    pub fn to_value(&self) -> Value {
+        self.ops.iter().map(|op| op.to_value(true)).collect()
+    }
+
+    /// Same as [`to_value`], but without `old` values, the RFC 6902 layout
+    ///
+    /// Moves of a table key to itself only reorder keys,
+    /// they are skipped because JSON objects are unordered, so key order is lost.
+    ///
+    /// [`to_value`]: Patch::to_value
+    pub fn to_json_patch(&self) -> Value {
+        self.ops.iter()
+            .filter(|op| !matches!(op, Operation::Move { from, path } if from == path))
+            .map(|op| op.to_value(false))
+            .collect()
+    }
+
+    /// Same as [`to_json_patch`], but convert into [`json::JsonValue`]
+    ///
+    /// [`to_json_patch`]: Patch::to_json_patch
+    #[cfg(feature = "json")]
+    pub fn to_json_patch_json(&self) -> json::JsonValue {
+        self.to_json_patch().to_json()
+    }
+
+    /// Same as [`to_json_patch`], but convert into [`serde_json::Value`]
+    ///
+    /// [`to_json_patch`]: Patch::to_json_patch
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_patch_serde_json(&self) -> serde_json::Value {
+        self.to_json_patch().to_serde_json()
+    }
+
+    /// Load from [`to_value`] or [`to_json_patch`] layout
+    ///
+    /// [`to_value`]: Patch::to_value
+    /// [`to_json_patch`]: Patch::to_json_patch
+    pub fn from_value(value: &Value) -> Result<Self, PatchError> {
+        let arr = value.as_array().ok_or_else(|| PatchError {
+            index: 0,
+            message: "expected array".into(),
+        })?;
+        arr.iter()
+            .enumerate()
+            .map(|(index, op)| {
+                Operation::from_value(op)
+                    .map_err(|message| PatchError { index, message })
+            })
+            .collect::<Result<_, _>>()
+            .map(|ops| Self { ops })
+    }
+}
+impl From<Vec<Operation>> for Patch {
+    fn from(ops: Vec<Operation>) -> Self {
+        Self { ops }
+    }
+}
+impl FromIterator<Operation> for Patch {
+    fn from_iter<T: IntoIterator<Item = Operation>>(iter: T) -> Self {
+        Self { ops: iter.into_iter().collect() }
+    }
+}
+impl SYMLSerialize for Patch {
+    fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
+        self.to_value().serialize_min(f)
+    }
+    fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
+        self.to_value().serialize(f, indent)
+    }
+}
+
+/// Compute the changes from a value to another
+///
+/// - Tables keep the key order of `to`, reordered keys are moved to the end
+/// - Arrays skip the common prefix and suffix, other items are changed by index
+/// - A removed value equal to an added value is a move,
+///   if both paths are not in arrays
+/// - Tagged values with the same tag are compared by inner value
+pub fn diff(from: &Value, to: &Value) -> Patch {
+    let mut ops = vec![];
+    diff_value(&Path::new(), from, to, &mut ops);
+    pair_moves(&mut ops);
+    Patch { ops }
+}
+
+fn diff_value(path: &Path, a: &Value, b: &Value, ops: &mut Vec<Operation>) {
+    if a == b {
+        return;
+    }
+    match (a, b) {
+        (Value::Table(a), Value::Table(b)) => diff_table(path, a, b, ops),
+        (Value::Array(a), Value::Array(b)) => diff_array(path, a, b, ops),
+        (Value::Tagged(a), Value::Tagged(b)) if a.tag == b.tag => {
+            diff_value(path, &a.value, &b.value, ops);
+        },
+        _ => ops.push(Operation::Replace {
+            path: path.clone(),
+            old: a.clone(),
+            value: b.clone(),
+        }),
+    }
+}
+
+fn diff_table(path: &Path, a: &Table, b: &Table, ops: &mut Vec<Operation>) {
+    for (k, v) in a {
+        if !b.contains_key(k) {
+            ops.push(Operation::Remove { path: path.join(k.as_str()), old: v.clone() });
+        }
+    }
+    // Keys are moved to the end in order, unmoved keys must be a prefix of `b`
+    let mut current = a.keys().filter(|k| b.contains_key(*k));
+    let unmoved = b.keys()
+        .take_while(|k| a.contains_key(*k) && current.any(|cur| cur == *k))
+        .count();
+    for (i, (k, v)) in b.iter().enumerate() {
+        let key_path = path.join(k.as_str());
+        match a.get(k) {
+            Some(old) => {
+                if i >= unmoved {
+                    ops.push(Operation::Move { from: key_path.clone(), path: key_path.clone() });
+                }
+                diff_value(&key_path, old, v, ops);
+            },
+            None => ops.push(Operation::Add { path: key_path, value: v.clone() }),
+        }
+    }
+}
+
+fn diff_array(path: &Path, a: &[Value], b: &[Value], ops: &mut Vec<Operation>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a_rest, b_rest) = (&a[prefix..], &b[prefix..]);
+    let suffix = a_rest.iter().rev().zip(b_rest.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a_rest[..a_rest.len() - suffix];
+    let b_mid = &b_rest[..b_rest.len() - suffix];
+    let common = a_mid.len().min(b_mid.len());
+    for (i, (x, y)) in a_mid.iter().zip(b_mid).enumerate() {
+        diff_value(&path.join(prefix + i), x, y, ops);
+    }
+    for i in (common..a_mid.len()).rev() {
+        ops.push(Operation::Remove { path: path.join(prefix + i), old: a_mid[i].clone() });
+    }
+    for (i, value) in b_mid.iter().enumerate().skip(common) {
+        ops.push(Operation::Add { path: path.join(prefix + i), value: value.clone() });
+    }
+}
+
+fn is_keys_only(path: &Path) -> bool {
+    path.segments().iter().all(|seg| matches!(seg, Segment::Key(_)))
+}
+
+/// Merge removes and adds of the same value into moves
+fn pair_moves(ops: &mut Vec<Operation>) {
+    let mut i = 0;
+    while i < ops.len() {
+        let Operation::Add { path, value } = &ops[i] else {
+            i += 1;
+            continue;
+        };
+        let found = is_keys_only(path).then(|| ops[..i].iter().position(|op| {
+            matches!(op, Operation::Remove { path: from, old }
+                if old == value && is_keys_only(from))
+        })).flatten();
+        let Some(j) = found else {
+            i += 1;
+            continue;
+        };
+        let Operation::Remove { path: from, .. } = ops.remove(j) else { unreachable!() };
+        i -= 1;
+        let Operation::Add { path, .. } = &ops[i] else { unreachable!() };
+        ops[i] = Operation::Move { from, path: path.clone() };
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    fn check(from: &str, to: &str) -> Patch {
+        let (from, to) = (parser::value(from).unwrap(), parser::value(to).unwrap());
+        let patch = diff(&from, &to);
+        let mut value = from.clone();
+        patch.apply(&mut value).unwrap();
+        assert_eq!(value, to, "{patch:#?}");
+        let loaded = Patch::from_value(&patch.to_value()).unwrap();
+        assert_eq!(loaded.to_value(), patch.to_value());
+        let mut value = from.clone();
+        loaded.apply(&mut value).unwrap();
+        assert_eq!(value, to);
+        patch
+    }
+
+    #[test]
+    fn diff_apply_test() {
+        let tests = [
+            ("a: 1", "a: 1"),
+            ("a: 1", "b: 1"),
+            ("a: 1\nb: 2\nc: 3", "c: 3\na: 1\nb: 2"),
+            ("a: 1\nb: 2\nc: 3", "b: 2\na: 1\nd: 4\nc: 3"),
+            ("a: 1\nb: 2\nc: 3", "a: 1\nc: 4\nb: 2"),
+            ("a:\n  b: [1, 2, 3]", "a:\n  b: [0, 1, 3, 4]"),
+            ("a: [1, 2, 3]", "a: [3, 2, 1]"),
+            ("a: [1, 2, 3]", "a: []"),
+            ("a: [x, {b: 1}]", "a: [y, {b: 2, c: 3}]"),
+            ("a: !t {b: 1}", "a: !t {b: 2}"),
+            ("a: !t {b: 1}", "a: !u {b: 1}"),
+            ("a: {b: {c: 1}}", "a: {d: {c: 1}}\ne: {c: 1}"),
+            ("a: [{b: 1}]", "c: {b: 1}"),
+            ("a: ~", "a: [1]"),
+            ("[1, 2]", "x"),
+            ("a: 1", "[a]"),
+        ];
+        for (from, to) in tests {
+            check(from, to);
+        }
+    }
+
+    #[test]
+    fn operations_test() {
+        let patch = check("a: 1\nb: 2\nc: {d: 3}", "b: 2\nx: {d: 3}\na: 1");
+        assert_eq!(patch.to_json_patch(), parser::value("\
+- {op: move, from: /c, path: /x}
+").unwrap());
+        // `BTreeMap` is sorted, so keys are never reordered
+        let reorder = Operation::Move { from: "a".parse().unwrap(), path: "a".parse().unwrap() };
+        assert_eq!(patch.operations().contains(&reorder), !cfg!(feature = "btreemap"));
+        let patch = check("a: [1, 2]", "a: [1, 3, 2]");
+        assert_eq!(patch.operations(), [
+            Operation::Add { path: "a[1]".parse().unwrap(), value: "3".into() },
+        ]);
+        let patch = check("a: [1, 2]", "b: [1, 2]");
+        assert_eq!(patch.operations(), [
+            Operation::Move { from: "a".parse().unwrap(), path: "b".parse().unwrap() },
+        ]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_patch_test() {
+        let patch = check("a: 1\nb: [x]", "a: 2\nb: [x, y]");
+        assert_eq!(patch.to_json_patch_json().dump(), concat!(
+            r#"[{"op":"replace","path":"/a","value":"2"},"#,
+            r#"{"op":"add","path":"/b/1","value":"y"}]"#,
+        ));
+    }
+
+    #[test]
+    fn apply_error_test() {
+        let patch = Patch::from_value(&parser::value("\
+- {op: add, path: /a/-, value: 3}
+- {op: add, path: /b/c, value: 4}
+- {op: remove, path: /x}
+").unwrap()).unwrap();
+        let mut value = parser::value("a: [1, 2]\nb: ~").unwrap();
+        let err = patch.apply(&mut value).unwrap_err();
+        assert_eq!(err.index, 1);
+        assert_eq!(value, parser::value("a: [1, 2]\nb: ~").unwrap());
+
+        let mut value = parser::value("a: [1, 2]\nb: {}").unwrap();
+        let err = patch.apply(&mut value).unwrap_err();
+        assert_eq!(err.index, 2);
+
+        let tests = [
+            "- {op: copy, path: /a}",
+            "- {op: add, path: a, value: 1}",
+            "- {op: add, path: /a}",
+            "- {op: move, path: /a}",
+            "- [1]",
+        ];
+        for src in tests {
+            let value = parser::value(src).unwrap();
+            assert!(Patch::from_value(&value).is_err(), "{src:?}");
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Events of a document, read by top-level entries
+//!
+//! This is a chunked reader, not a streaming parser:
+//! each top-level entry of a block table or list is read and parsed whole,
+//! then its events are yielded, the whole [`Value`] tree is never built.
+//! A single-value document (e.g `[...]` or `{...}`) is one entry,
+//! so memory usage is bounded by the largest entry,
+//! split large documents into many top-level entries or list items.
+//!
+//! [`Value`]: crate::Value
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    error,
+    fmt,
+    io::{self, BufRead},
+    mem,
+    ops::Range,
+};
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{
+    chunk::{is_blank, shift_error, Chunker, DocKind},
+    parser::{self, Key, Node},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event {
+    StartTable,
+    /// Key of the next value in table
+    Key(String),
+    StartArray,
+    Scalar(String),
+    Null,
+    /// Tag of the next value
+    Tag(String),
+    /// End of table or array
+    End,
+}
+
+/// Event and its byte range of the source
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpannedEvent {
+    pub event: Event,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ParseError<LineCol>),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::Parse(e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse(e) => Some(e),
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl From<ParseError<LineCol>> for Error {
+    fn from(value: ParseError<LineCol>) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// Events of a parsed chunk
+pub(crate) struct Events(pub(crate) Vec<SpannedEvent>);
+impl Events {
+    fn one(event: Event) -> Self {
+        Self(vec![SpannedEvent { event, span: 0..0 }])
+    }
+}
+impl<'a> Node<'a> for Events {
+    fn plain(s: &'a str) -> Self {
+        Self::one(Event::Scalar(s.into()))
+    }
+    fn quoted(s: Cow<'a, str>) -> Self {
+        Self::one(Event::Scalar(s.into_owned()))
+    }
+    fn array(arr: Vec<Self>) -> Self {
+        let mut events = Self::one(Event::StartArray);
+        events.0.extend(arr.into_iter().flat_map(|v| v.0));
+        events.0.push(SpannedEvent { event: Event::End, span: 0..0 });
+        events
+    }
+    fn table(entries: Vec<(Key<'a>, Self)>) -> Self {
+        let mut events = Self::one(Event::StartTable);
+        for (k, v) in entries {
+            events.0.push(SpannedEvent {
+                event: Event::Key(k.name.into_owned()),
+                span: k.span,
+            });
+            events.0.extend(v.0);
+        }
+        events.0.push(SpannedEvent { event: Event::End, span: 0..0 });
+        events
+    }
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        let mut events = Self::one(Event::Tag(tag.into()));
+        events.0.extend(value.0);
+        events
+    }
+    fn null() -> Self {
+        Self::one(Event::Null)
+    }
+    fn spanned(mut self, span: Range<usize>) -> Self {
+        let [first, .., last] = &mut self.0[..] else {
+            self.0[0].span = span;
+            return self;
+        };
+        if matches!(first.event, Event::StartTable | Event::StartArray) {
+            last.span = span.end..span.end;
+        }
+        first.span = span;
+        self
+    }
+}
+
+/// Reader of events by top-level entries, see the [module-level documentation](self)
+///
+/// Duplicate keys of table are all yielded,
+/// and after an error the iteration ends.
+///
+/// The span of top-level block table or list start event
+/// only cover the first entry.
+///
+/// # Examples
+/// ```
+/// use syml::events::{EntryReader, Event};
+///
+/// let src = "a: 1\nb:\n- x\n- [y]\n";
+/// let events = EntryReader::new(src.as_bytes())
+///     .map(|e| e.unwrap().event)
+///     .collect::<Vec<_>>();
+/// assert_eq!(events, [
+///     Event::StartTable,
+///     Event::Key("a".into()),
+///     Event::Scalar("1".into()),
+///     Event::Key("b".into()),
+///     Event::StartArray,
+///     Event::Scalar("x".into()),
+///     Event::StartArray,
+///     Event::Scalar("y".into()),
+///     Event::End,
+///     Event::End,
+///     Event::End,
+/// ]);
+/// ```
+#[derive(Debug)]
+pub struct EntryReader<R> {
+    reader: R,
+    chunker: Chunker,
+    events: VecDeque<SpannedEvent>,
+    /// Start of next chunk, read from the reader
+    next_line: String,
+    /// Bytes and lines of the source before `next_line`
+    offset: usize,
+    line: usize,
+    finished: bool,
+}
+impl<R: BufRead> EntryReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chunker: Chunker::default(),
+            events: VecDeque::new(),
+            next_line: String::new(),
+            offset: 0,
+            line: 1,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read the next chunk, returns empty string on eof
+    fn read_chunk(&mut self) -> io::Result<String> {
+        let mut chunk = mem::take(&mut self.next_line);
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(chunk);
+            }
+            if self.chunker.feed(&line) {
+                self.next_line = line;
+                return Ok(chunk);
+            }
+            chunk.push_str(&line);
+        }
+    }
+
+    fn parse_chunk(
+        &mut self,
+        chunk: &str,
+        first_chunk: bool,
+    ) -> Result<Events, ParseError<LineCol>> {
+        if first_chunk {
+            let events: Events = parser::node(chunk)?;
+            let start = chunk.lines()
+                .find(|line| !is_blank(line))
+                .unwrap_or_default();
+            self.chunker.kind = Some(match events.0[0].event {
+                Event::StartArray if start.starts_with("- ") => DocKind::List,
+                Event::StartTable if !start.starts_with('{') => DocKind::Table,
+                _ => DocKind::Single,
+            });
+            return Ok(events);
+        }
+        match self.chunker.kind {
+            Some(DocKind::Table) => parser::table_node(chunk),
+            Some(DocKind::List) => parser::list_node(chunk),
+            _ => parser::empty(chunk).map(|()| Events(vec![])),
+        }
+    }
+
+    fn next_chunk(&mut self) -> Result<(), Error> {
+        let chunk = self.read_chunk()?;
+        let first_chunk = self.offset == 0;
+        let block = matches!(self.chunker.kind, Some(DocKind::Table | DocKind::List));
+        if chunk.is_empty() && !first_chunk {
+            if block {
+                self.events.push_back(SpannedEvent {
+                    event: Event::End,
+                    span: self.offset..self.offset,
+                });
+            }
+            self.finished = true;
+            return Ok(());
+        }
+        let Events(mut events) = self.parse_chunk(&chunk, first_chunk)
+            .map_err(|e| shift_error(e, self.offset, self.line))?;
+        let block = matches!(self.chunker.kind, Some(DocKind::Table | DocKind::List));
+        let range = match (block, first_chunk) {
+            (true, true) => 0..events.len()-1,
+            (true, false) => 1..events.len()-1,
+            (false, _) => 0..events.len(),
+        };
+        let offset = self.offset;
+        self.events.extend(events.drain(range).map(|mut event| {
+            event.span.start += offset;
+            event.span.end += offset;
+            event
+        }));
+        self.offset += chunk.len();
+        self.line += chunk.matches('\n').count();
+        Ok(())
+    }
+}
+impl<R: BufRead> Iterator for EntryReader<R> {
+    type Item = Result<SpannedEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() && !self.finished {
+            if let Err(e) = self.next_chunk() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::BufReader, iter::Peekable, vec};
+
+    use crate::{parser, Value};
+    use super::*;
+
+    fn build(events: &mut Peekable<vec::IntoIter<Event>>) -> Value {
+        match events.next().unwrap() {
+            Event::StartTable => {
+                let mut entries = vec![];
+                loop {
+                    match events.next().unwrap() {
+                        Event::Key(k) => entries.push((k, build(events))),
+                        Event::End => break entries.into_iter().collect(),
+                        e => panic!("unexpected {e:?}"),
+                    }
+                }
+            },
+            Event::StartArray => {
+                let mut arr = vec![];
+                while events.next_if_eq(&Event::End).is_none() {
+                    arr.push(build(events));
+                }
+                arr.into()
+            },
+            Event::Scalar(s) => s.into(),
+            Event::Null => Value::Null,
+            Event::Tag(tag) => Value::tagged(tag, build(events)),
+            e => panic!("unexpected {e:?}"),
+        }
+    }
+
+    fn parse(src: &str) -> Result<Value, Error> {
+        let reader = BufReader::with_capacity(3, src.as_bytes());
+        let events = EntryReader::new(reader)
+            .map(|event| event.map(|e| e.event))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut iter = events.into_iter().peekable();
+        let value = build(&mut iter);
+        assert_eq!(iter.next(), None);
+        Ok(value)
+    }
+
+    #[test]
+    fn same_as_value_test() {
+        let mut tests = vec![
+            "a", "a\n", "[a, b]", "{a: 1}", "~", "!t [a]",
+            "; c\n\na: 1\n; c\nb:\n- 1\n- 2\n\nc: 3",
+            "- a\n- b: 1\n  c: 2\n- - x\n  - y\n",
+            "a: \"x\\\n;c\ny: 1\"\nb: 'it''s'\nc: it's \"\n",
+            "a: 'b\"c'\nd: e",
+            "a:\n  b: 1\n  c:\n  - 2\nd: 3",
+            "a: !t\n  b: 1\nc: ~",
+            "a: 1\na: 2",
+            "\n\n- 1\n",
+            "", "; only comment\n", "a\nb", "[a]\nb: 1", "{a: 1}\nb: 2",
+            "- 1\nb: 2", "a: 1\n- 2", "a: 1\n b: 2", "a: 1\n\tb: 2",
+            "- 1\n  - 2", "a: \"x\\\nb: 1",
+        ];
+        let mut datas = vec![];
+        for entry in fs::read_dir("tests/parse_datas").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "syml") {
+                datas.push(fs::read_to_string(path).unwrap());
+            }
+        }
+        tests.extend(datas.iter().map(String::as_str));
+        for src in tests {
+            let expected = parser::value(src);
+            match (parse(src), expected) {
+                (Ok(value), Ok(expected)) if value == expected => (),
+                (Err(Error::Parse(e)), Err(expected)) => {
+                    assert_eq!(e.location.line, expected.location.line, "{src:?}");
+                },
+                (value, expected) => {
+                    panic!("{src:?}:\n{value:?}\n{expected:?}")
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn span_test() {
+        let src = "a: 1\nbc:\n- 'x'\n- [y]\n";
+        let events = EntryReader::new(src.as_bytes())
+            .map(|e| {
+                let e = e.unwrap();
+                (e.event, &src[e.span])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events, [
+            (Event::StartTable, "a: 1"),
+            (Event::Key("a".into()), "a"),
+            (Event::Scalar("1".into()), "1"),
+            (Event::Key("bc".into()), "bc"),
+            (Event::StartArray, "- 'x'\n- [y]"),
+            (Event::Scalar("x".into()), "'x'"),
+            (Event::StartArray, "[y]"),
+            (Event::Scalar("y".into()), "y"),
+            (Event::End, ""),
+            (Event::End, ""),
+            (Event::End, ""),
+        ]);
+    }
+}
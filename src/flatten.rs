@@ -0,0 +1,394 @@
+use std::{error::Error, fmt};
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::{Path, Segment, Table, Value};
+
+/// How array indices are written in flattened keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IndexNotation {
+    /// Index is a segment, e.g `a.0.b`,
+    /// tables of keys `0..n` are unflattened into arrays
+    #[default]
+    Segment,
+    /// Index is in brackets after the key, e.g `a[0].b`
+    Bracket,
+    /// Same as [`Segment`], but always unflattened into tables
+    ///
+    /// [`Segment`]: IndexNotation::Segment
+    TableOnly,
+}
+
+/// Options of [`Value::flatten_with`] and [`Value::unflatten_with`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlattenOptions {
+    /// Separator of keys, default `.`
+    pub sep: String,
+    pub index: IndexNotation,
+}
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self { sep: ".".into(), index: IndexNotation::default() }
+    }
+}
+impl FlattenOptions {
+    pub fn new(sep: impl Into<String>) -> Self {
+        Self { sep: sep.into(), ..Default::default() }
+    }
+
+    pub fn index(mut self, index: IndexNotation) -> Self {
+        self.index = index;
+        self
+    }
+
+    fn key(&self, path: &Path) -> String {
+        let mut key = String::new();
+        for (i, seg) in path.segments().iter().enumerate() {
+            match (seg, self.index) {
+                (Segment::Index(index), IndexNotation::Bracket) => {
+                    key.push_str(&format!("[{index}]"));
+                    continue;
+                },
+                _ if i != 0 => key.push_str(&self.sep),
+                _ => (),
+            }
+            match seg {
+                Segment::Key(k) => key.push_str(k),
+                Segment::Index(index) => key.push_str(&index.to_string()),
+            }
+        }
+        key
+    }
+
+    fn path(&self, key: &str) -> Path {
+        let parts: Vec<&str> = if self.sep.is_empty() {
+            vec![key]
+        } else {
+            key.split(self.sep.as_str()).collect()
+        };
+        let mut path = Path::new();
+        for part in parts {
+            if self.index != IndexNotation::Bracket {
+                path.push(part);
+                continue;
+            }
+            let mut rest = part;
+            let mut indices = vec![];
+            while let Some((s, i)) = rest.strip_suffix(']')
+                .and_then(|s| s.rsplit_once('['))
+                .and_then(|(s, i)| Some((s, i.parse::<usize>().ok().filter(|n| n.to_string() == i)?)))
+            {
+                indices.push(i);
+                rest = s;
+            }
+            if !rest.is_empty() || indices.is_empty() {
+                path.push(rest);
+            }
+            indices.into_iter().rev().for_each(|i| path.push(i));
+        }
+        path
+    }
+}
+
+/// Conflicted or invalid flattened keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnflattenError {
+    /// Key of the failed entry
+    pub key: String,
+    pub message: String,
+}
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {}", self.key, self.message)
+    }
+}
+impl Error for UnflattenError { }
+
+/// Unflattened tree, nodes keep the first key for errors
+enum Node {
+    Leaf(String, Value),
+    Branch(String, LinkedHashMap<Segment, Node>),
+}
+impl Node {
+    fn key(&self) -> &str {
+        match self {
+            Node::Leaf(key, _) | Node::Branch(key, _) => key,
+        }
+    }
+
+    fn insert(&mut self, segs: &[Segment], key: String, value: Value) -> Result<(), UnflattenError> {
+        let conflict = |other: &str| Err(UnflattenError {
+            message: format!("conflicts with `{other}`"),
+            key: key.clone(),
+        });
+        let Node::Branch(_, map) = self else {
+            return conflict(self.key());
+        };
+        let (seg, rest) = segs.split_first().unwrap();
+        match (map.get_mut(seg), rest) {
+            (Some(node), []) => conflict(node.key()),
+            (Some(node), rest) => node.insert(rest, key, value),
+            (None, []) => {
+                map.insert(seg.clone(), Node::Leaf(key, value));
+                Ok(())
+            },
+            (None, rest) => {
+                let mut node = Node::Branch(key.clone(), LinkedHashMap::new());
+                node.insert(rest, key, value)?;
+                map.insert(seg.clone(), node);
+                Ok(())
+            },
+        }
+    }
+
+    fn into_value(self, index: IndexNotation) -> Result<Value, UnflattenError> {
+        let (key, map) = match self {
+            Node::Leaf(_, value) => return Ok(value),
+            Node::Branch(key, map) => (key, map),
+        };
+        let error = |message: String| UnflattenError { key: key.clone(), message };
+        let indices = map.keys()
+            .map(|seg| match (seg, index) {
+                (Segment::Index(i), _) => Some(*i),
+                (Segment::Key(k), IndexNotation::Segment) => {
+                    k.parse().ok().filter(|i: &usize| i.to_string() == *k)
+                },
+                _ => None,
+            })
+            .collect::<Option<Vec<usize>>>();
+        if let Some(indices) = indices {
+            // indices are distinct, so they are `0..len` if all are seen
+            let mut seen = vec![false; indices.len()];
+            for &i in &indices {
+                if let Some(seen) = seen.get_mut(i) {
+                    *seen = true;
+                }
+            }
+            match seen.iter().position(|seen| !seen) {
+                None => {
+                    let mut items = vec![None; indices.len()];
+                    for (i, (_, node)) in indices.into_iter().zip(map) {
+                        items[i] = Some(node.into_value(index)?);
+                    }
+                    return Ok(Value::Array(items.into_iter().flatten().collect()));
+                },
+                Some(missing) if index == IndexNotation::Bracket => {
+                    return Err(error(format!("array index gap, missing index {missing}")));
+                },
+                // not an array, e.g keys `0` and `2`
+                Some(_) => (),
+            }
+        }
+        if map.keys().any(|seg| matches!(seg, Segment::Index(_))) {
+            return Err(error("array index mixed with table key".into()));
+        }
+        map.into_iter()
+            .map(|(seg, node)| {
+                let Segment::Key(k) = seg else { unreachable!() };
+                Ok((k, node.into_value(index)?))
+            })
+            .collect::<Result<Table, _>>()
+            .map(Value::Table)
+    }
+}
+
+fn unflatten<V: Into<Value>>(
+    entries: impl IntoIterator<Item = (String, Path, V)>,
+    index: IndexNotation,
+) -> Result<Value, UnflattenError> {
+    let mut root = Node::Branch(String::new(), LinkedHashMap::new());
+    for (key, path, value) in entries {
+        if path.is_empty() {
+            return Err(UnflattenError { key, message: "empty path".into() });
+        }
+        root.insert(path.segments(), key, value.into())?;
+    }
+    root.into_value(index)
+}
+
+impl Value {
+    /// Flatten string scalars into map of their paths
+    ///
+    /// Table keys containing `sep` are split into segments,
+    /// the same as keys joined by `sep` are read by [`unflatten_with`].
+    /// Tags are skipped, `~`, empty arrays and empty tables are omitted.
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Path, parser};
+    ///
+    /// let value = parser::value("db: {hosts: [a, b], 'x.y': 80}").unwrap();
+    /// let flat: Vec<_> = value.flatten(".").into_iter().collect();
+    /// assert_eq!(flat, [
+    ///     (Path::from_iter(["db", "hosts"]).join(0), "a".into()),
+    ///     (Path::from_iter(["db", "hosts"]).join(1), "b".into()),
+    ///     (Path::from_iter(["db", "x", "y"]), "80".into()),
+    /// ]);
+    /// ```
+    ///
+    /// [`unflatten_with`]: Value::unflatten_with
+    pub fn flatten(&self, sep: &str) -> LinkedHashMap<Path, String> {
+        self.leaves()
+            .map(|(path, s)| {
+                let mut split = Path::new();
+                for seg in path.segments() {
+                    match seg {
+                        Segment::Key(k) if !sep.is_empty() => k.split(sep).for_each(|k| split.push(k)),
+                        seg => split.push(seg.clone()),
+                    }
+                }
+                (split, s.to_owned())
+            })
+            .collect()
+    }
+
+    /// Flatten string scalars into `key=value` map, keys are joined by [`FlattenOptions::sep`]
+    ///
+    /// Tags are skipped, `~`, empty arrays and empty tables are omitted.
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{FlattenOptions, IndexNotation, parser};
+    ///
+    /// let value = parser::value("db: {hosts: [a, b], port: 80}").unwrap();
+    /// let options = FlattenOptions::new("__").index(IndexNotation::Bracket);
+    /// let flat = value.flatten_with(&options);
+    /// let flat: Vec<_> = flat.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    /// assert_eq!(flat, ["db__hosts[0]=a", "db__hosts[1]=b", "db__port=80"]);
+    /// ```
+    pub fn flatten_with(&self, options: &FlattenOptions) -> LinkedHashMap<String, String> {
+        self.leaves()
+            .map(|(path, s)| (options.key(&path), s.to_owned()))
+            .collect()
+    }
+
+    /// Inverse of [`flatten`], index segments are arrays and key segments are tables,
+    /// use [`unflatten_with`] to choose the [`IndexNotation`] of string keys
+    ///
+    /// Keys containing `sep` were split by [`flatten`], so they are unflattened into nested tables,
+    /// the same as [`unflatten_with`] reads the keys of [`flatten_with`].
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let value = parser::value("a.b: [x]").unwrap();
+    /// let value = Value::unflatten(value.flatten("."));
+    /// assert_eq!(value, Ok(parser::value("a: {b: [x]}").unwrap()));
+    /// ```
+    ///
+    /// [`flatten`]: Value::flatten
+    /// [`flatten_with`]: Value::flatten_with
+    /// [`unflatten_with`]: Value::unflatten_with
+    pub fn unflatten<V: Into<Self>>(
+        entries: impl IntoIterator<Item = (Path, V)>,
+    ) -> Result<Self, UnflattenError> {
+        let entries = entries.into_iter()
+            .map(|(path, v)| (path.to_string(), path, v));
+        unflatten(entries, IndexNotation::Bracket)
+    }
+
+    /// Inverse of [`flatten_with`], rebuild tables and arrays
+    ///
+    /// A key conflicts with a longer key prefixed by it, or a same key.
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{FlattenOptions, Value, parser};
+    ///
+    /// let options = FlattenOptions::new(".");
+    /// let value = Value::unflatten_with([("a.1", "y"), ("a.0", "x"), ("b", "z")], &options);
+    /// assert_eq!(value, Ok(parser::value("a: [x, y]\nb: z").unwrap()));
+    ///
+    /// let err = Value::unflatten_with([("a", "1"), ("a.b", "2")], &options).unwrap_err();
+    /// assert_eq!(err.to_string(), "`a.b`: conflicts with `a`");
+    /// ```
+    ///
+    /// [`flatten_with`]: Value::flatten_with
+    pub fn unflatten_with<K, V>(
+        entries: impl IntoIterator<Item = (K, V)>,
+        options: &FlattenOptions,
+    ) -> Result<Self, UnflattenError>
+    where K: AsRef<str>,
+          V: Into<Self>,
+    {
+        let entries = entries.into_iter()
+            .map(|(k, v)| {
+                let key = k.as_ref();
+                (key.to_owned(), options.path(key), v)
+            });
+        unflatten(entries, options.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn flatten_test() {
+        let value = parser::value("\
+a: [x, [y], {b: z}]
+c: !t {'d.e': ~, f: []}
+g: w
+").unwrap();
+        let tests = [
+            (IndexNotation::Segment, [("a.0", "x"), ("a.1.0", "y"), ("a.2.b", "z"), ("g", "w")]),
+            (IndexNotation::Bracket, [("a[0]", "x"), ("a[1][0]", "y"), ("a[2].b", "z"), ("g", "w")]),
+        ];
+        for (index, expected) in tests {
+            let options = FlattenOptions::new(".").index(index);
+            let flat = value.flatten_with(&options);
+            let flat: Vec<_> = flat.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            assert_eq!(flat, expected);
+            let expected = parser::value("a: [x, [y], {b: z}]\ng: w").unwrap();
+            assert_eq!(Value::unflatten_with(flat, &options), Ok(expected));
+        }
+        let options = FlattenOptions::new("/").index(IndexNotation::Bracket);
+        assert_eq!(Value::unflatten(value.flatten("/")), Value::unflatten_with(value.flatten_with(&options), &options));
+        let value = parser::value("a.b: 1
+c: {'d.e': [2]}").unwrap();
+        let flat = value.flatten(".");
+        assert_eq!(flat.keys().map(Path::to_string).collect::<Vec<_>>(), ["a.b", "c.d.e[0]"]);
+        assert_eq!(Value::unflatten(flat), Ok(parser::value("a: {b: 1}\nc: {d: {e: [2]}}").unwrap()));
+        assert_eq!(Value::from("x").flatten("."), LinkedHashMap::from_iter([(Path::new(), "x".into())]));
+    }
+
+    #[test]
+    fn unflatten_test() {
+        let tests = [
+            (IndexNotation::Segment, vec![("a.1", "x"), ("a.0", "y")], "a: [y, x]"),
+            (IndexNotation::Segment, vec![("a.0", "x"), ("a.2", "y")], "a: {0: x, 2: y}"),
+            (IndexNotation::Segment, vec![("a.01", "x"), ("a.0", "y")], "a: {01: x, 0: y}"),
+            (IndexNotation::TableOnly, vec![("a.0", "x")], "a: {0: x}"),
+            (IndexNotation::Bracket, vec![("a[1]", "x"), ("a[0]", "y")], "a: [y, x]"),
+            (IndexNotation::Bracket, vec![("[0].a", "x"), ("[1]", "y")], "[{a: x}, y]"),
+            (IndexNotation::Bracket, vec![("a.0", "x"), ("a[x]", "y")], "a: {0: x}\n'a[x]': y"),
+        ];
+        for (index, entries, expected) in tests {
+            let options = FlattenOptions::new(".").index(index);
+            let value = Value::unflatten_with(entries.clone(), &options);
+            assert_eq!(value, Ok(parser::value(expected).unwrap()), "{entries:?}");
+        }
+        let value = Value::unflatten_with([("A__B", "1"), ("A__C__0", "2")], &FlattenOptions::new("__"));
+        assert_eq!(value, Ok(parser::value("A: {B: 1, C: [2]}").unwrap()));
+    }
+
+    #[test]
+    fn unflatten_error_test() {
+        let tests = [
+            (IndexNotation::Segment, vec![("a", "1"), ("a.b", "2")], "`a.b`: conflicts with `a`"),
+            (IndexNotation::Segment, vec![("a.b.c", "1"), ("a.b", "2")], "`a.b`: conflicts with `a.b.c`"),
+            (IndexNotation::Segment, vec![("a", "1"), ("a", "2")], "`a`: conflicts with `a`"),
+            (IndexNotation::Bracket, vec![("a[0]", "1"), ("a[5]", "2")], "`a[0]`: array index gap, missing index 1"),
+            (IndexNotation::Bracket, vec![("a[2]", "1"), ("a[1]", "2")], "`a[2]`: array index gap, missing index 0"),
+            (IndexNotation::Bracket, vec![("a[0]", "1"), ("a.b", "2")], "`a[0]`: array index mixed with table key"),
+        ];
+        for (index, entries, message) in tests {
+            let options = FlattenOptions::new(".").index(index);
+            let err = Value::unflatten_with(entries.clone(), &options).unwrap_err();
+            assert_eq!(err.to_string(), message, "{entries:?}");
+        }
+    }
+}
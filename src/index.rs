@@ -0,0 +1,178 @@
+use std::ops;
+
+use crate::{Table, Value};
+
+/// Returned by indexing a missing entry
+static ABSENT: Value = Value::Null;
+
+mod private {
+    pub trait Sealed { }
+    impl Sealed for usize { }
+    impl Sealed for str { }
+    impl Sealed for String { }
+    impl<T: Sealed + ?Sized> Sealed for &T { }
+}
+
+/// Key of table or index of array, used by [`Value::get`] and indexing
+///
+/// Tags of the indexed value are skipped.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value>;
+
+    /// Panic if the entry can not be inserted
+    #[doc(hidden)]
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value;
+}
+impl Index for usize {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.untagged().as_array()?.get(*self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        value.untagged_mut().as_array_mut()?.get_mut(*self)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        match value.untagged_mut() {
+            Value::Array(arr) => {
+                let len = arr.len();
+                arr.get_mut(*self).unwrap_or_else(|| {
+                    panic!("index {self} out of range for array of length {len}")
+                })
+            },
+            value => panic!("cannot index {} with usize", value.kind()),
+        }
+    }
+}
+impl Index for str {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.untagged().as_table()?.get(self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        value.untagged_mut().as_table_mut()?.get_mut(self)
+    }
+
+    /// [`Null`] is changed into an empty table
+    ///
+    /// [`Null`]: Value::Null
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        let value = value.untagged_mut();
+        if value.is_null() {
+            *value = Table::new().into();
+        }
+        match value {
+            Value::Table(table) => {
+                table.entry(self.to_owned()).or_insert(Value::Null)
+            },
+            value => panic!("cannot index {} with string", value.kind()),
+        }
+    }
+}
+impl Index for String {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_str().index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        self.as_str().index_or_insert(value)
+    }
+}
+impl<T: Index + ?Sized> Index for &T {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        (**self).index_or_insert(value)
+    }
+}
+
+/// Missing entries and mismatched types returns [`Value::Null`]
+///
+/// # Examples
+/// ```
+/// use syml::{Value, parser};
+///
+/// let value = parser::value("a:\n- x\n- y").unwrap();
+/// assert_eq!(value["a"][1], Value::from("y"));
+/// assert_eq!(value["a"][2], Value::Null);
+/// assert_eq!(value["b"]["c"], Value::Null);
+/// ```
+impl<I: Index> ops::Index<I> for Value {
+    type Output = Value;
+
+    fn index(&self, index: I) -> &Self::Output {
+        index.index_into(self).unwrap_or(&ABSENT)
+    }
+}
+/// Insert [`Value::Null`] for missing key,
+/// [`Value::Null`] indexed by key is changed into an empty table
+///
+/// # Panics
+/// - Index array out of range
+/// - Index by key on non table, or index by integer on non array
+///
+/// # Examples
+/// ```
+/// use syml::{Value, parser};
+///
+/// let mut value = Value::Null;
+/// value["a"]["b"] = "c".into();
+/// assert_eq!(value, parser::value("a:\n  b: c").unwrap());
+/// ```
+impl<I: Index> ops::IndexMut<I> for Value {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        index.index_or_insert(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn index_test() {
+        let value = parser::value("a: !t\n  b: [1, {c: 2}]\nd: x").unwrap();
+        assert_eq!(value["a"]["b"][1]["c"], Value::from("2"));
+        assert_eq!(value["a"]["b"][1][0], Value::Null);
+        assert_eq!(value["d"][0], Value::Null);
+        assert_eq!(value[0], Value::Null);
+        assert_eq!(value[&"d".to_owned()], Value::from("x"));
+        assert_eq!(value.get("a").and_then(|a| a.get("b")).map(Value::is_array), Some(true));
+        assert_eq!(value.get("e"), None);
+        assert_eq!(value.get(0), None);
+    }
+
+    #[test]
+    fn index_mut_test() {
+        let mut value = parser::value("a: [1, 2]\nb: !t ~").unwrap();
+        value["a"][0] = "x".into();
+        value["b"]["c"] = "y".into();
+        assert_eq!(value.get_mut("d"), None);
+        if let Some(a) = value.get_mut("a") {
+            a.as_array_mut().unwrap().push("3".into());
+        }
+        assert_eq!(value, parser::value("a: [x, 2, 3]\nb: !t {c: y}").unwrap());
+    }
+
+    #[test]
+    #[should_panic = "out of range"]
+    fn index_mut_panic_test() {
+        let mut value = Value::from(["a"]);
+        value[1] = "b".into();
+    }
+}
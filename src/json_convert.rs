@@ -0,0 +1,255 @@
+//! Conversions between [`Value`](crate::Value) and JSON values of `json` and `serde_json`,
+//! tags are dropped when converting into JSON
+
+/// Coercion of strings when converting into JSON, all disabled by default
+///
+/// Explicit `~` is always JSON null
+///
+/// # Examples
+/// ```
+/// use syml::{JsonOptions, parser};
+///
+/// let value = parser::value("[1.5, 'true', 'null', x]").unwrap();
+/// let json = value.to_json_with(&JsonOptions::weak());
+/// assert_eq!(json.dump(), r#"[1.5,true,null,"x"]"#);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct JsonOptions {
+    /// Convert strings of finite `f64` into numbers, e.g `-n`
+    pub number: bool,
+    /// Convert `true` and `false` into booleans, e.g `-b`
+    pub boolean: bool,
+    /// Convert `null` into null, e.g `-N`
+    pub null: bool,
+}
+impl JsonOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable all coercions, e.g `-w`
+    pub fn weak() -> Self {
+        Self { number: true, boolean: true, null: true }
+    }
+
+    pub fn number(mut self, yes: bool) -> Self {
+        self.number = yes;
+        self
+    }
+
+    pub fn boolean(mut self, yes: bool) -> Self {
+        self.boolean = yes;
+        self
+    }
+
+    pub fn null(mut self, yes: bool) -> Self {
+        self.null = yes;
+        self
+    }
+}
+
+/// String coerced by [`JsonOptions`]
+enum Scalar<'a> {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(&'a str),
+}
+
+impl JsonOptions {
+    fn scalar<'a>(&self, s: &'a str) -> Scalar<'a> {
+        if self.null && s == "null" {
+            return Scalar::Null;
+        }
+        if let Some(b) = self.boolean.then(|| s.parse().ok()).flatten() {
+            return Scalar::Boolean(b);
+        }
+        match self.number.then(|| s.parse::<f64>().ok()).flatten() {
+            Some(n) if n.is_finite() => Scalar::Number(n),
+            _ => Scalar::String(s),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+mod json_impl {
+    use json::JsonValue;
+
+    use super::{JsonOptions, Scalar};
+    use crate::Value;
+
+    impl Value {
+        /// Convert into [`json::JsonValue`], strings are not coerced
+        pub fn to_json(&self) -> JsonValue {
+            self.to_json_with(&JsonOptions::default())
+        }
+
+        /// Convert into [`json::JsonValue`], strings are coerced by `options`
+        pub fn to_json_with(&self, options: &JsonOptions) -> JsonValue {
+            match self {
+                Value::String(s) => match options.scalar(s) {
+                    Scalar::Null => JsonValue::Null,
+                    Scalar::Boolean(b) => JsonValue::Boolean(b),
+                    Scalar::Number(n) => JsonValue::Number(n.into()),
+                    Scalar::String(s) => JsonValue::String(s.to_owned()),
+                },
+                Value::Array(arr) => {
+                    JsonValue::Array(arr.iter()
+                        .map(|item| item.to_json_with(options))
+                        .collect())
+                },
+                Value::Table(table) => {
+                    let mut object = json::object::Object::with_capacity(table.len());
+                    for (k, v) in table {
+                        object.insert(k, v.to_json_with(options));
+                    }
+                    JsonValue::Object(object)
+                },
+                Value::Tagged(tagged) => tagged.value.to_json_with(options),
+                Value::Null => JsonValue::Null,
+            }
+        }
+    }
+
+    impl From<Value> for JsonValue {
+        /// Same as [`Value::to_json`]
+        fn from(value: Value) -> Self {
+            value.to_json()
+        }
+    }
+
+    impl From<JsonValue> for Value {
+        /// Booleans and numbers are converted into strings
+        fn from(value: JsonValue) -> Self {
+            match value {
+                JsonValue::Null => Value::Null,
+                JsonValue::Boolean(b) => b.into(),
+                JsonValue::Number(n) => n.to_string().into(),
+                JsonValue::Short(s) => s.as_str().into(),
+                JsonValue::String(s) => s.into(),
+                JsonValue::Array(arr) => Value::Array(arr.into_iter().map(Into::into).collect()),
+                mut object @ JsonValue::Object(_) => {
+                    Value::Table(object.entries_mut()
+                        .map(|(k, v)| (k.to_owned(), v.take().into()))
+                        .collect())
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+mod serde_json_impl {
+    use serde_json::{Number, Value as JsonValue};
+
+    use super::{JsonOptions, Scalar};
+    use crate::Value;
+
+    impl Value {
+        /// Convert into [`serde_json::Value`], strings are not coerced
+        pub fn to_serde_json(&self) -> JsonValue {
+            self.to_serde_json_with(&JsonOptions::default())
+        }
+
+        /// Convert into [`serde_json::Value`], strings are coerced by `options`
+        ///
+        /// Integers are kept exactly if they fit in `i64` or `u64`
+        pub fn to_serde_json_with(&self, options: &JsonOptions) -> JsonValue {
+            match self {
+                Value::String(s) => match options.scalar(s) {
+                    Scalar::Null => JsonValue::Null,
+                    Scalar::Boolean(b) => JsonValue::Bool(b),
+                    Scalar::Number(n) => {
+                        let number = s.parse::<i64>().map(Number::from)
+                            .or_else(|_| s.parse::<u64>().map(Number::from))
+                            .ok()
+                            .or_else(|| Number::from_f64(n));
+                        number.map_or_else(|| JsonValue::String(s.to_owned()), JsonValue::Number)
+                    },
+                    Scalar::String(s) => JsonValue::String(s.to_owned()),
+                },
+                Value::Array(arr) => {
+                    JsonValue::Array(arr.iter()
+                        .map(|item| item.to_serde_json_with(options))
+                        .collect())
+                },
+                Value::Table(table) => {
+                    JsonValue::Object(table.iter()
+                        .map(|(k, v)| (k.clone(), v.to_serde_json_with(options)))
+                        .collect())
+                },
+                Value::Tagged(tagged) => tagged.value.to_serde_json_with(options),
+                Value::Null => JsonValue::Null,
+            }
+        }
+    }
+
+    impl From<Value> for JsonValue {
+        /// Same as [`Value::to_serde_json`]
+        fn from(value: Value) -> Self {
+            value.to_serde_json()
+        }
+    }
+
+    impl From<JsonValue> for Value {
+        /// Booleans and numbers are converted into strings
+        fn from(value: JsonValue) -> Self {
+            match value {
+                JsonValue::Null => Value::Null,
+                JsonValue::Bool(b) => b.into(),
+                JsonValue::Number(n) => n.to_string().into(),
+                JsonValue::String(s) => s.into(),
+                JsonValue::Array(arr) => Value::Array(arr.into_iter().map(Into::into).collect()),
+                JsonValue::Object(object) => {
+                    Value::Table(object.into_iter()
+                        .map(|(k, v)| (k, v.into()))
+                        .collect())
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser, Value};
+    use super::*;
+
+    const SRC: &str = "\
+a: [1, -2, 1.5, 1e400, 18446744073709551615, true, 'null', ~]
+b: !t {c: x}
+";
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_test() {
+        let value = parser::value(SRC).unwrap();
+        let tests = [
+            (JsonOptions::new(), r#"{"a":["1","-2","1.5","1e400","18446744073709551615","true","null",null],"b":{"c":"x"}}"#),
+            (JsonOptions::new().boolean(true).null(true), r#"{"a":["1","-2","1.5","1e400","18446744073709551615",true,null,null],"b":{"c":"x"}}"#),
+            (JsonOptions::new().number(true), r#"{"a":[1,-2,1.5,"1e400",18446744073709553000,"true","null",null],"b":{"c":"x"}}"#),
+        ];
+        for (options, expected) in tests {
+            assert_eq!(value.to_json_with(&options).dump(), expected, "{options:?}");
+        }
+        let json = json::parse(r#"{"a": [1, 2.5, true, null, "x"], "b": {}}"#).unwrap();
+        assert_eq!(Value::from(json), parser::value("a: [1, 2.5, true, ~, x]\nb: {}").unwrap());
+        assert_eq!(json::JsonValue::from(Value::from(["x"])), json::array!["x"]);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn serde_json_test() {
+        let value = parser::value(SRC).unwrap();
+        let tests = [
+            (JsonOptions::new(), r#"{"a":["1","-2","1.5","1e400","18446744073709551615","true","null",null],"b":{"c":"x"}}"#),
+            (JsonOptions::weak(), r#"{"a":[1,-2,1.5,"1e400",18446744073709551615,true,null,null],"b":{"c":"x"}}"#),
+        ];
+        for (options, expected) in tests {
+            assert_eq!(value.to_serde_json_with(&options).to_string(), expected, "{options:?}");
+        }
+        let json: serde_json::Value = serde_json::from_str(r#"{"a": [1, 2.5, true, null, "x"], "b": {}}"#).unwrap();
+        assert_eq!(Value::from(json), parser::value("a: [1, 2.5, true, ~, x]\nb: {}").unwrap());
+        assert_eq!(serde_json::Value::from(Value::from(["x"])), serde_json::json!(["x"]));
+    }
+}
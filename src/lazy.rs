@@ -0,0 +1,322 @@
+//! Lazy parsed document
+//!
+//! Only the top-level structure is indexed by the indentation rules,
+//! each top-level entry is parsed on the first access.
+
+use std::{cell::OnceCell, ops::Range};
+
+use linked_hash_map::LinkedHashMap;
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{
+    chunk::{shift_error, Chunker, DocKind},
+    parser::{self, fast},
+    Value,
+};
+
+#[derive(Debug)]
+struct Entry {
+    range: Range<usize>,
+    /// Line number of the entry start
+    line: usize,
+    value: OnceCell<Value>,
+}
+
+#[derive(Debug)]
+enum Index {
+    /// Key to the first entry of the key
+    Table(Vec<Entry>, LinkedHashMap<String, usize>),
+    List(Vec<Entry>),
+    /// Inline value, parsed on creation
+    Single(Value),
+}
+
+/// Document whose top-level entries are parsed on demand
+///
+/// Syntax errors inside an entry are reported on the first access of the entry,
+/// the error locations are relative to the whole source.
+///
+/// # Examples
+/// ```
+/// use syml::{Value, lazy::LazyDocument};
+///
+/// let doc = LazyDocument::new("a: 1\nb:\n  c: [2, 3]\nd: [\n").unwrap();
+/// assert_eq!(doc.keys().collect::<Vec<_>>(), ["a", "b", "d"]);
+/// assert_eq!(doc.get("a").unwrap(), Some(&Value::from("1")));
+/// assert_eq!(doc.get_path(["b", "c", "1"]).unwrap(), Some(&Value::from("3")));
+/// assert!(doc.get("d").is_err());
+/// ```
+#[derive(Debug)]
+pub struct LazyDocument<S> {
+    src: S,
+    index: Index,
+}
+impl<S: AsRef<str>> LazyDocument<S> {
+    /// Index the top-level structure of the source
+    ///
+    /// Inline documents are parsed immediately.
+    pub fn new(src: S) -> Result<Self, ParseError<LineCol>> {
+        let text = src.as_ref();
+        let mut chunker = Chunker::default();
+        let mut entries = vec![];
+        let (mut offset, mut line) = (0, 1);
+        let (mut start, mut start_line) = (0, 1);
+        for ln in text.split_inclusive('\n') {
+            if chunker.feed(ln) {
+                entries.push(Entry::new(start..offset, start_line));
+                (start, start_line) = (offset, line);
+            }
+            offset += ln.len();
+            line += 1;
+        }
+        entries.push(Entry::new(start..offset, start_line));
+
+        let index = match chunker.kind {
+            Some(DocKind::List) => Index::List(entries),
+            Some(DocKind::Table)
+                if fast::table_key(&text[entries[0].range.clone()]).is_some() =>
+            {
+                let mut keys = LinkedHashMap::with_capacity(entries.len());
+                for (i, entry) in entries.iter().enumerate() {
+                    let chunk = &text[entry.range.clone()];
+                    let Some(key) = fast::table_key(chunk) else {
+                        let e = parser::table_node::<Value>(chunk)
+                            .expect_err("chunk without key");
+                        return Err(shift_error(e, entry.range.start, entry.line));
+                    };
+                    keys.entry(key.name.into_owned()).or_insert(i);
+                }
+                Index::Table(entries, keys)
+            },
+            _ => Index::Single(fast::value(text)?),
+        };
+        Ok(Self { src, index })
+    }
+
+    pub fn source(&self) -> &str {
+        self.src.as_ref()
+    }
+
+    pub fn into_source(self) -> S {
+        self.src
+    }
+
+    fn parse_entry<'a>(&'a self, entry: &'a Entry) -> Result<&'a Value, ParseError<LineCol>> {
+        if let Some(value) = entry.value.get() {
+            return Ok(value);
+        }
+        let value = fast::value(&self.source()[entry.range.clone()])
+            .map_err(|e| shift_error(e, entry.range.start, entry.line))?;
+        let value = match value {
+            Value::Table(table) => table.into_iter().next().map(|(_, v)| v),
+            Value::Array(arr) => arr.into_iter().next(),
+            _ => None,
+        };
+        Ok(entry.value.get_or_init(|| value.unwrap_or(Value::Null)))
+    }
+
+    /// Top-level keys, empty if top-level is not a table
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        let (lazy, single) = match &self.index {
+            Index::Table(_, keys) => (Some(keys.keys()), None),
+            Index::Single(Value::Table(table)) => (None, Some(table.keys())),
+            _ => (None, None),
+        };
+        lazy.into_iter().flatten()
+            .chain(single.into_iter().flatten())
+            .map(String::as_str)
+    }
+
+    /// Count of top-level entries or items, 0 if top-level is a string
+    pub fn len(&self) -> usize {
+        match &self.index {
+            Index::Table(_, keys) => keys.len(),
+            Index::List(entries) => entries.len(),
+            Index::Single(Value::Table(table)) => table.len(),
+            Index::Single(Value::Array(arr)) => arr.len(),
+            Index::Single(_) => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get top-level table value, parse it if not yet parsed
+    pub fn get(&self, key: &str) -> Result<Option<&Value>, ParseError<LineCol>> {
+        match &self.index {
+            Index::Table(entries, keys) => {
+                keys.get(key)
+                    .map(|&i| self.parse_entry(&entries[i]))
+                    .transpose()
+            },
+            Index::Single(Value::Table(table)) => Ok(table.get(key)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get top-level list item, parse it if not yet parsed
+    pub fn get_index(&self, index: usize) -> Result<Option<&Value>, ParseError<LineCol>> {
+        match &self.index {
+            Index::List(entries) => {
+                entries.get(index)
+                    .map(|entry| self.parse_entry(entry))
+                    .transpose()
+            },
+            Index::Single(Value::Array(arr)) => Ok(arr.get(index)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get value by keys and indices, only the first top-level entry is parsed
+    ///
+    /// Segments on arrays are parsed as index.
+    pub fn get_path<I>(&self, path: I) -> Result<Option<&Value>, ParseError<LineCol>>
+    where I: IntoIterator,
+          I::Item: AsRef<str>,
+    {
+        let mut path = path.into_iter();
+        let Some(first) = path.next() else {
+            return Ok(None);
+        };
+        let first = first.as_ref();
+        let value = match &self.index {
+            Index::List(_) | Index::Single(Value::Array(_)) => {
+                let Ok(i) = first.parse() else { return Ok(None) };
+                self.get_index(i)?
+            },
+            _ => self.get(first)?,
+        };
+        Ok(value.and_then(|value| path.try_fold(value, |value, seg| {
+            let seg = seg.as_ref();
+            match value.untagged() {
+                Value::Table(table) => table.get(seg),
+                Value::Array(arr) => arr.get(seg.parse::<usize>().ok()?),
+                _ => None,
+            }
+        })))
+    }
+
+    /// Parse all entries and build the whole value
+    pub fn to_value(&self) -> Result<Value, ParseError<LineCol>> {
+        Ok(match &self.index {
+            Index::Table(entries, keys) => {
+                for entry in entries {
+                    self.parse_entry(entry)?;
+                }
+                keys.iter()
+                    .map(|(k, &i)| Ok((k.clone(), self.parse_entry(&entries[i])?.clone())))
+                    .collect::<Result<_, _>>()?
+            },
+            Index::List(entries) => {
+                entries.iter()
+                    .map(|entry| self.parse_entry(entry).cloned())
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            },
+            Index::Single(value) => value.clone(),
+        })
+    }
+}
+impl Entry {
+    fn new(range: Range<usize>, line: usize) -> Self {
+        Self { range, line, value: OnceCell::new() }
+    }
+}
+
+/// Memory-mapped UTF-8 file, used as the source of [`LazyDocument`]
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MmapSource(memmap2::Mmap);
+#[cfg(feature = "mmap")]
+impl MmapSource {
+    /// Map the file and validate it is UTF-8
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped,
+    /// see [`memmap2::Mmap::map`]
+    pub unsafe fn open(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        use std::io;
+
+        let file = std::fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        std::str::from_utf8(&map)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self(map))
+    }
+}
+#[cfg(feature = "mmap")]
+impl AsRef<str> for MmapSource {
+    fn as_ref(&self) -> &str {
+        // SAFETY: validated on open
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn same_as_value_test() {
+        let mut tests = vec![
+            "a", "[a, b]", "{a: 1}", "~", "!t [a]", "",
+            "; c\n\na: 1\n; c\nb:\n- 1\n- 2\n\nc: 3",
+            "- a\n- b: 1\n  c: 2\n- - x\n  - y\n",
+            "a: \"x\\\n;c\ny: 1\"\nb: 'it''s'\n",
+            "a: !t\n  b: 1\nc: ~\nd:",
+            "a: 1\na: 2", "a: 1\na: [", "a: 1\n b: 2", "- 1\nb: 2",
+        ];
+        let mut datas = vec![];
+        for entry in fs::read_dir("tests/parse_datas").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "syml") {
+                datas.push(fs::read_to_string(path).unwrap());
+            }
+        }
+        tests.extend(datas.iter().map(String::as_str));
+        for src in tests {
+            let doc = LazyDocument::new(src).and_then(|doc| doc.to_value());
+            match (doc, parser::value(src)) {
+                (Err(e), Err(expected)) => {
+                    assert_eq!(e.location, expected.location, "{src:?}");
+                },
+                (doc, expected) => assert_eq!(doc, expected, "{src:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_test() {
+        let src = "a: 1\nb:\n- x\n- [y\nc: {d: 2}";
+        let doc = LazyDocument::new(src).unwrap();
+        assert_eq!(doc.len(), 3);
+        assert_eq!(doc.get_path(["c", "d"]), Ok(Some(&"2".into())));
+        assert_eq!(doc.get_path(["c", "e"]), Ok(None));
+        assert_eq!(doc.get("x"), Ok(None));
+        let Index::Table(entries, _) = &doc.index else { panic!() };
+        let parsed = entries.iter()
+            .map(|entry| entry.value.get().is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, [false, false, true]);
+
+        let e = doc.get("b").unwrap_err();
+        assert_eq!(e.location, parser::value(src).unwrap_err().location);
+        assert_eq!(e.location.line, 4);
+
+        let doc = LazyDocument::new("- a\n- - b\n  - c").unwrap();
+        assert_eq!(doc.get_path(["1", "1"]), Ok(Some(&"c".into())));
+        assert_eq!(doc.get_path(["x"]), Ok(None));
+    }
+
+    #[test]
+    fn index_error_test() {
+        for src in ["a: 1\n[b]", "a: 1\n\n'b: 2", "[a]\nb: 1", "[a"] {
+            let e = LazyDocument::new(src).unwrap_err();
+            let expected = parser::value(src).unwrap_err();
+            assert_eq!(e.location, expected.location, "{src:?}");
+        }
+    }
+}
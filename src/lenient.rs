@@ -0,0 +1,492 @@
+//! Lenient conversions of scalar strings, for human written configs
+//!
+//! - Duration: `30s`, `1h30m`, `1.5s`, `100ms`,
+//!   units are `ns`, `us`/`µs`, `ms`, `s`, `m`/`min`, `h` and `d`
+//! - Byte size: `512MiB`, `1.5GB`, `64k`, `100`,
+//!   units are case insensitive, `k`/`kB` is 1000 and `Ki`/`KiB` is 1024, up to `E`
+//! - Integer: `0xff`, `0o17`, `0b101`, `1_000_000`
+//! - Bool: words of [`BoolWords`], e.g `yes`, `off`
+//!
+//! Use [`Lenient`] and [`ByteSize`] with [`FromSyml`] and the accessors of [`Value`],
+//! or with serde by the `serde_impl` feature.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use syml::{lenient::{ByteSize, Lenient}, parser};
+//!
+//! let value = parser::value("\
+//! timeout: 1m30s
+//! cache: 512MiB
+//! mask: 0xff
+//! enabled: yes
+//! ").unwrap();
+//! assert_eq!(value.require::<Lenient<Duration>>("timeout").unwrap().0, Duration::from_secs(90));
+//! assert_eq!(value.require::<ByteSize>("cache").unwrap().0, 512 << 20);
+//! assert_eq!(value.require::<Lenient<u8>>("mask").unwrap().0, 0xff);
+//! assert!(value.require::<Lenient<bool>>("enabled").unwrap().0);
+//! ```
+//!
+//! [`FromSyml`]: crate::FromSyml
+
+use std::{error::Error, fmt, num::ParseIntError, time::Duration};
+
+use crate::{FromSyml, FromSymlError, Kind, Value};
+
+/// Invalid lenient scalar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientError {
+    pub message: String,
+}
+impl LenientError {
+    fn new(message: impl fmt::Display) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+impl fmt::Display for LenientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+impl Error for LenientError { }
+
+/// Split a decimal number with `_` separators from the start,
+/// returns integer digits, fraction digits and the rest
+fn split_number(s: &str) -> (String, String, &str) {
+    let end = s.find(|ch: char| !(ch.is_ascii_digit() || ch == '.' || ch == '_'))
+        .unwrap_or(s.len());
+    let (number, rest) = s.split_at(end);
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    let digits = |s: &str| s.chars().filter(|&ch| ch != '_').collect();
+    (digits(int), digits(frac), rest)
+}
+
+/// `int.frac * unit`, fraction is truncated
+fn scale(int: &str, frac: &str, unit: u128) -> Option<u128> {
+    if (int.is_empty() && frac.is_empty()) || frac.contains('.') {
+        return None;
+    }
+    let int: u128 = if int.is_empty() { 0 } else { int.parse().ok()? };
+    let frac_len = u32::try_from(frac.len()).ok().filter(|&n| n <= 30)?;
+    let frac: u128 = if frac.is_empty() { 0 } else { frac.parse().ok()? };
+    let frac = frac.checked_mul(unit)? / 10u128.pow(frac_len);
+    int.checked_mul(unit)?.checked_add(frac)
+}
+
+/// Parse duration, e.g `1h30m`, `1.5s`, spaces between parts are allowed
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use syml::lenient::parse_duration;
+///
+/// assert_eq!(parse_duration("1h 30m"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+/// assert!(parse_duration("30").is_err());
+/// ```
+pub fn parse_duration(s: &str) -> Result<Duration, LenientError> {
+    let mut rest = s.trim();
+    if rest == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if rest.is_empty() {
+        return Err(LenientError::new("empty duration"));
+    }
+    let mut nanos = 0u128;
+    while !rest.is_empty() {
+        let (int, frac, tail) = split_number(rest);
+        let unit_len = tail.find(|ch: char| !ch.is_alphabetic()).unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let unit: u128 = match unit {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" | "min" => 60_000_000_000,
+            "h" => 3_600_000_000_000,
+            "d" => 86_400_000_000_000,
+            "" => return Err(LenientError::new("missing duration unit")),
+            unit => return Err(LenientError::new(format_args!("unknown duration unit `{unit}`"))),
+        };
+        nanos = scale(&int, &frac, unit)
+            .and_then(|n| nanos.checked_add(n))
+            .ok_or_else(|| LenientError::new("invalid or too large duration"))?;
+        rest = tail.trim_start();
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000)
+        .map_err(|_| LenientError::new("invalid or too large duration"))?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// Parse byte size, e.g `512MiB`, `1.5 GB`, `100`, fraction bytes are truncated
+///
+/// # Examples
+/// ```
+/// use syml::lenient::parse_size;
+///
+/// assert_eq!(parse_size("512MiB"), Ok(512 * 1024 * 1024));
+/// assert_eq!(parse_size("1.5 kb"), Ok(1500));
+/// assert_eq!(parse_size("64"), Ok(64));
+/// ```
+pub fn parse_size(s: &str) -> Result<u64, LenientError> {
+    let (int, frac, unit) = split_number(s.trim());
+    let unit = unit.trim_start().to_ascii_lowercase();
+    let unit = unit.strip_suffix('b').unwrap_or(&unit);
+    let (prefix, base) = match unit.strip_suffix('i') {
+        Some(prefix) if !prefix.is_empty() => (prefix, 1024u128),
+        _ => (unit, 1000),
+    };
+    let exp = match prefix {
+        "" if base == 1000 => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        "e" => 6,
+        _ => return Err(LenientError::new(format_args!("unknown size unit `{unit}`"))),
+    };
+    scale(&int, &frac, base.pow(exp))
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or_else(|| LenientError::new("invalid or too large size"))
+}
+
+/// Integer types of [`parse_int`]
+pub trait LenientInt: Sized {
+    #[doc(hidden)]
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+}
+macro_rules! impl_lenient_int {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl LenientInt for $ty {
+                fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
+                    <$ty>::from_str_radix(src, radix)
+                }
+            }
+        )+
+    };
+}
+impl_lenient_int! {
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+}
+
+/// Parse integer with `0x`, `0o` or `0b` prefix and `_` separators
+///
+/// # Examples
+/// ```
+/// use syml::lenient::parse_int;
+///
+/// assert_eq!(parse_int::<u8>("0xff"), Ok(255));
+/// assert_eq!(parse_int::<i32>("-0b1_01"), Ok(-5));
+/// assert_eq!(parse_int::<u32>("1_000"), Ok(1000));
+/// assert!(parse_int::<u8>("0x1_00").is_err());
+/// ```
+pub fn parse_int<T: LenientInt>(s: &str) -> Result<T, LenientError> {
+    let s = s.trim();
+    let (sign, unsigned) = match s.as_bytes().first() {
+        Some(b'-') => ("-", &s[1..]),
+        Some(b'+') => ("", &s[1..]),
+        _ => ("", s),
+    };
+    let prefix = unsigned.get(..2).map(str::to_ascii_lowercase);
+    let (radix, digits) = match prefix.as_deref() {
+        Some("0x") => (16, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0b") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+    if digits.starts_with(['_', '+', '-']) || digits.ends_with('_') {
+        return Err(LenientError::new("invalid digit found in string"));
+    }
+    let digits = digits.replace('_', "");
+    T::from_str_radix(&format!("{sign}{digits}"), radix).map_err(LenientError::new)
+}
+
+/// Words of true and false, compared case insensitively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoolWords<'a> {
+    pub truthy: &'a [&'a str],
+    pub falsy: &'a [&'a str],
+}
+impl BoolWords<'_> {
+    /// `true`, `yes`, `y`, `on`, `1` and `false`, `no`, `n`, `off`, `0`
+    pub const DEFAULT: BoolWords<'static> = BoolWords {
+        truthy: &["true", "yes", "y", "on", "1"],
+        falsy: &["false", "no", "n", "off", "0"],
+    };
+
+    /// # Examples
+    /// ```
+    /// use syml::lenient::BoolWords;
+    ///
+    /// let words = BoolWords { truthy: &["enabled"], falsy: &["disabled"] };
+    /// assert_eq!(words.parse("Enabled"), Ok(true));
+    /// assert!(words.parse("yes").is_err());
+    /// assert_eq!(BoolWords::DEFAULT.parse("OFF"), Ok(false));
+    /// ```
+    pub fn parse(&self, s: &str) -> Result<bool, LenientError> {
+        let s = s.trim();
+        let is = |words: &[&str]| words.iter().any(|word| word.eq_ignore_ascii_case(s));
+        if is(self.truthy) {
+            Ok(true)
+        } else if is(self.falsy) {
+            Ok(false)
+        } else {
+            Err(LenientError::new(format_args!("expected one of {}",
+                self.truthy.iter().chain(self.falsy)
+                    .map(|word| format!("`{word}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "))))
+        }
+    }
+
+    /// Convert string value by [`parse`], tags are skipped
+    ///
+    /// [`parse`]: BoolWords::parse
+    pub fn convert(&self, value: &Value) -> Result<bool, FromSymlError> {
+        let s = value.untagged().as_str()
+            .ok_or_else(|| FromSymlError::mismatch(Kind::String, value))?;
+        self.parse(s).map_err(|e| FromSymlError::parse("bool", s, e))
+    }
+}
+impl Default for BoolWords<'_> {
+    fn default() -> Self {
+        BoolWords::DEFAULT
+    }
+}
+
+/// Types parsed leniently by [`Lenient`]
+pub trait LenientParse: Sized {
+    /// Type name in errors
+    const NAME: &'static str;
+
+    fn parse_lenient(s: &str) -> Result<Self, LenientError>;
+}
+macro_rules! impl_lenient_parse_int {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl LenientParse for $ty {
+                const NAME: &'static str = stringify!($ty);
+
+                fn parse_lenient(s: &str) -> Result<Self, LenientError> {
+                    parse_int(s)
+                }
+            }
+        )+
+    };
+}
+impl_lenient_parse_int! {
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+}
+impl LenientParse for bool {
+    const NAME: &'static str = "bool";
+
+    /// Use [`BoolWords::DEFAULT`]
+    fn parse_lenient(s: &str) -> Result<Self, LenientError> {
+        BoolWords::DEFAULT.parse(s)
+    }
+}
+impl LenientParse for Duration {
+    const NAME: &'static str = "duration";
+
+    fn parse_lenient(s: &str) -> Result<Self, LenientError> {
+        parse_duration(s)
+    }
+}
+
+fn convert<T>(value: &Value, name: &'static str, parse: impl FnOnce(&str) -> Result<T, LenientError>)
+    -> Result<T, FromSymlError>
+{
+    let s = value.untagged().as_str()
+        .ok_or_else(|| FromSymlError::mismatch(Kind::String, value))?;
+    parse(s).map_err(|e| FromSymlError::parse(name, s, e))
+}
+
+/// Leniently parsed integer, bool or [`Duration`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lenient<T>(pub T);
+impl<T: LenientParse> FromSyml for Lenient<T> {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        convert(value, T::NAME, T::parse_lenient).map(Self)
+    }
+}
+
+/// Byte size parsed by [`parse_size`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+impl FromSyml for ByteSize {
+    fn from_syml(value: &Value) -> Result<Self, FromSymlError> {
+        convert(value, "byte size", parse_size).map(Self)
+    }
+}
+
+#[cfg(feature = "serde_impl")]
+mod serde_impl {
+    use std::fmt;
+
+    use serde::{de, Deserialize, Deserializer};
+
+    use super::*;
+
+    /// Accept strings, and other scalars by their text
+    struct Visitor<T>(&'static str, fn(&str) -> Result<T, LenientError>);
+    impl<T> de::Visitor<'_> for Visitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a lenient {}", self.0)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            self.1(v).map_err(|e| E::custom(format_args!("invalid {} `{v}`: {e}", self.0)))
+        }
+
+        fn visit_bool<E: de::Error>(self, v: bool) -> Result<T, E> {
+            self.visit_str(&v.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+            self.visit_str(&v.to_string())
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+            self.visit_str(&v.to_string())
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+            self.visit_str(&v.to_string())
+        }
+    }
+
+    impl<'de, T: LenientParse> Deserialize<'de> for Lenient<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(Visitor(T::NAME, T::parse_lenient)).map(Self)
+        }
+    }
+    impl<'de> Deserialize<'de> for ByteSize {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(Visitor("byte size", parse_size)).map(Self)
+        }
+    }
+
+    /// For `#[serde(deserialize_with = "syml::lenient::deserialize")]`
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where D: Deserializer<'de>,
+          T: LenientParse,
+    {
+        Lenient::<T>::deserialize(deserializer).map(|Lenient(v)| v)
+    }
+
+    /// For `#[serde(deserialize_with = "syml::lenient::deserialize_size")]`
+    pub fn deserialize_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where D: Deserializer<'de>,
+    {
+        ByteSize::deserialize(deserializer).map(|ByteSize(v)| v)
+    }
+}
+#[cfg(feature = "serde_impl")]
+pub use serde_impl::{deserialize, deserialize_size};
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn duration_test() {
+        let tests = [
+            ("0", Some(Duration::ZERO)),
+            ("30s", Some(Duration::from_secs(30))),
+            ("1h30m", Some(Duration::from_secs(5400))),
+            ("1d 2h 3min 4s", Some(Duration::from_secs(93784))),
+            ("1.5s", Some(Duration::from_millis(1500))),
+            (".5ms", Some(Duration::from_micros(500))),
+            ("100ms", Some(Duration::from_millis(100))),
+            ("3us 2ns", Some(Duration::from_nanos(3002))),
+            ("2µs", Some(Duration::from_micros(2))),
+            ("1_000ms", Some(Duration::from_secs(1))),
+            ("", None),
+            ("30", None),
+            ("5x", None),
+            ("s", None),
+            ("1.2.3s", None),
+            ("-1s", None),
+            ("99999999999999999999999d", None),
+        ];
+        for (src, expected) in tests {
+            assert_eq!(parse_duration(src).ok(), expected, "{src:?}");
+        }
+        assert_eq!(parse_duration("5x").unwrap_err().to_string(), "unknown duration unit `x`");
+    }
+
+    #[test]
+    fn size_test() {
+        let tests = [
+            ("100", Some(100)),
+            ("100B", Some(100)),
+            ("64k", Some(64_000)),
+            ("64KiB", Some(64 << 10)),
+            ("512MiB", Some(512 << 20)),
+            ("1.5 GB", Some(1_500_000_000)),
+            ("2gi", Some(2 << 30)),
+            ("1_024 kb", Some(1_024_000)),
+            ("1EiB", Some(1 << 60)),
+            ("16EiB", None),
+            ("1i", None),
+            ("1x", None),
+            ("MB", None),
+        ];
+        for (src, expected) in tests {
+            assert_eq!(parse_size(src).ok(), expected, "{src:?}");
+        }
+    }
+
+    #[test]
+    fn int_test() {
+        assert_eq!(parse_int::<u8>("0xFF"), Ok(255));
+        assert_eq!(parse_int::<u8>("0XfF"), Ok(255));
+        assert_eq!(parse_int::<u16>("0o17"), Ok(15));
+        assert_eq!(parse_int::<i8>("-0x80"), Ok(-128));
+        assert_eq!(parse_int::<i64>("+1_000"), Ok(1000));
+        assert_eq!(parse_int::<u8>("7"), Ok(7));
+        for src in ["0x", "0x_1", "1_", "--1", "0x-1", "-1", "256", "0b2", ""] {
+            assert!(parse_int::<u8>(src).is_err(), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn from_syml_test() {
+        let value = parser::value("a: [yes, Off]\nb: 1m\nc: 2k\nd: !t '1 KiB'\ne: [x]").unwrap();
+        assert_eq!(value.require::<Vec<Lenient<bool>>>("a"), Ok(vec![Lenient(true), Lenient(false)]));
+        assert_eq!(value.get_or("b", Lenient(Duration::ZERO)), Ok(Lenient(Duration::from_secs(60))));
+        assert_eq!(value.get_or("x", Lenient(Duration::ZERO)), Ok(Lenient(Duration::ZERO)));
+        assert_eq!(value.require::<ByteSize>("c"), Ok(ByteSize(2000)));
+        assert_eq!(value.require::<ByteSize>("d"), Ok(ByteSize(1024)));
+
+        let err = value.require::<Lenient<bool>>("e[0]").unwrap_err();
+        assert_eq!(err.to_string(), "e[0]: invalid bool `x`: expected one of \
+            `true`, `yes`, `y`, `on`, `1`, `false`, `no`, `n`, `off`, `0`");
+        let err = value.require::<ByteSize>("e").unwrap_err();
+        assert_eq!(err.to_string(), "e: expected string, found array");
+        let words = BoolWords { truthy: &["x"], falsy: &[] };
+        assert_eq!(words.convert(&value["e"][0]), Ok(true));
+        assert_eq!(words.convert(&value["a"][0]).unwrap_err().to_string(), "invalid bool `yes`: expected one of `x`");
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn serde_test() {
+        use serde::de::{value::Error, Deserialize, IntoDeserializer};
+
+        let de = IntoDeserializer::<Error>::into_deserializer;
+        assert_eq!(Lenient::<Duration>::deserialize(de("1m")).unwrap(), Lenient(Duration::from_secs(60)));
+        assert_eq!(deserialize::<_, u8>(de("0x10")).unwrap(), 16);
+        assert_eq!(deserialize_size(IntoDeserializer::<Error>::into_deserializer(512u64)).unwrap(), 512);
+        assert!(deserialize::<_, bool>(IntoDeserializer::<Error>::into_deserializer(true)).unwrap());
+        let err = deserialize::<_, bool>(de("x")).unwrap_err();
+        assert!(err.to_string().starts_with("invalid bool `x`: expected one of"), "{err}");
+    }
+}
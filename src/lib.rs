@@ -1,34 +1,143 @@
 #![doc = include_str!("../README.md")]
+#![allow(clippy::doc_overindented_list_items)]
 
 #[cfg(feature = "cli-utils")]
 pub mod cli_utils;
 
+mod borrowed;
+mod canonical;
+mod chunk;
+mod convert;
+mod diff;
+mod flatten;
+pub mod events;
+mod index;
+#[cfg(any(feature = "json", feature = "serde_json"))]
+mod json_convert;
+pub mod lazy;
+pub mod lenient;
+mod macros;
+mod merge;
 pub mod parser;
+mod path;
+pub mod query;
+#[cfg(feature = "serde_impl")]
+mod serde_impl;
 mod serialize;
-
-pub use serialize::SYMLSerialize;
-
-use linked_hash_map::LinkedHashMap;
-use core::{char, num, str};
-
-pub type Table = LinkedHashMap<String, Value>;
-
-#[cfg_attr(feature = "serde_impl", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+mod table;
+pub mod tags;
+mod typed;
+mod visit;
+
+pub use borrowed::{BorrowedTable, BorrowedTagged, BorrowedValue};
+pub use canonical::CanonicalOptions;
+pub use convert::{FromSyml, FromSymlError, FromSymlErrorKind, ToSyml};
+pub use diff::{diff, Operation, Patch, PatchError};
+pub use flatten::{FlattenOptions, IndexNotation, UnflattenError};
+pub use index::Index;
+#[cfg(any(feature = "json", feature = "serde_json"))]
+pub use json_convert::JsonOptions;
+#[doc(hidden)]
+pub use macros::private as __private;
+pub use merge::{ArrayMerge, MergeOptions};
+pub use path::{AsPath, Path, PathError, Segment};
+pub use serialize::{QuoteStyle, SerializeOptions, SYMLSerialize};
+pub use table::Table;
+pub use typed::{TypedTable, TypedValue};
+pub use visit::{visit_children, Leaves, VisitMut};
+
+use peg::{error::ParseError, str::LineCol};
+use core::{char, fmt, hash::{Hash, Hasher}, mem, num, str};
+use std::collections::HashMap;
+
+
+/// With the `serde_impl` feature, [`Value::Null`] is none for serde, e.g `Option<T>`
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
     String(String),
     Array(Vec<Self>),
     Table(Table),
+    /// Value with a type tag, e.g `!int 5`
+    Tagged(Box<Tagged>),
+    /// Empty value, e.g `~` or `key:`
+    Null,
+}
+
+/// A tag and the value it is attached to
+#[cfg_attr(feature = "serde_impl", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tagged {
+    pub tag: String,
+    pub value: Value,
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::String(s) => s.hash(state),
+            Value::Array(arr) => arr.hash(state),
+            Value::Table(table) => table::hash(table, state),
+            Value::Tagged(tagged) => tagged.hash(state),
+            Value::Null => (),
+        }
+    }
 }
+
+/// Kind of [`Value`] with tags skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    String,
+    Array,
+    Table,
+    Null,
+}
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::String => "string",
+            Kind::Array => "array",
+            Kind::Table => "table",
+            Kind::Null => "null",
+        })
+    }
+}
+
 impl Value {
+    /// Attach a tag to value
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let value = parser::value("!int 5").unwrap();
+    /// assert_eq!(value, Value::tagged("int", "5"));
+    /// ```
+    pub fn tagged(tag: impl Into<String>, value: impl Into<Self>) -> Self {
+        Self::Tagged(Box::new(Tagged {
+            tag: tag.into(),
+            value: value.into(),
+        }))
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             Value::String(s) => s.is_empty(),
             Value::Array(a) => a.is_empty(),
             Value::Table(t) => t.is_empty(),
+            Value::Tagged(t) => t.value.is_empty(),
+            Value::Null => true,
         }
     }
 
+    /// Returns `true` if the value is [`Null`].
+    ///
+    /// [`Null`]: Value::Null
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
     /// Returns `true` if the value is [`String`].
     ///
     /// [`String`]: Value::String
@@ -101,6 +210,101 @@ impl Value {
         }
     }
 
+    /// Returns `true` if the value is [`Tagged`].
+    ///
+    /// [`Tagged`]: Value::Tagged
+    #[must_use]
+    pub fn is_tagged(&self) -> bool {
+        matches!(self, Self::Tagged(..))
+    }
+
+    pub fn as_tagged(&self) -> Option<&Tagged> {
+        if let Self::Tagged(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_tagged_mut(&mut self) -> Option<&mut Tagged> {
+        if let Self::Tagged(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Get the kind of the untagged value
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Kind, Value};
+    ///
+    /// assert_eq!(Value::tagged("t", ["a"]).kind(), Kind::Array);
+    /// ```
+    pub fn kind(&self) -> Kind {
+        match self.untagged() {
+            Value::String(_) => Kind::String,
+            Value::Array(_) => Kind::Array,
+            Value::Table(_) => Kind::Table,
+            Value::Tagged(_) => unreachable!(),
+            Value::Null => Kind::Null,
+        }
+    }
+
+    /// Get the tag of value, if it is [`Tagged`]
+    ///
+    /// [`Tagged`]: Value::Tagged
+    pub fn tag(&self) -> Option<&str> {
+        Some(&self.as_tagged()?.tag)
+    }
+
+    /// Skip all tags, get the inner value
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::Value;
+    ///
+    /// let value = Value::tagged("a", Value::tagged("b", "c"));
+    /// assert_eq!(value.untagged(), &Value::from("c"));
+    /// ```
+    pub fn untagged(&self) -> &Self {
+        let mut this = self;
+        while let Self::Tagged(tagged) = this {
+            this = &tagged.value;
+        }
+        this
+    }
+
+    /// Skip all tags, get the inner value
+    pub fn untagged_mut(&mut self) -> &mut Self {
+        let mut this = self;
+        while let Self::Tagged(tagged) = this {
+            this = &mut tagged.value;
+        }
+        this
+    }
+
+    /// Get table value by key or array item by index, tags are skipped
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let value = parser::value("a: [x, y]").unwrap();
+    /// assert_eq!(value.get("a").and_then(|a| a.get(1)), Some(&Value::from("y")));
+    /// assert_eq!(value.get("b"), None);
+    /// assert_eq!(value.get(0), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Self> {
+        index.index_into(self)
+    }
+
+    /// Get mutable table value by key or array item by index, tags are skipped
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Self> {
+        index.index_into_mut(self)
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         Some(&**self.as_string()?)
     }
@@ -130,14 +334,39 @@ impl From<String> for Value {
         Self::String(value)
     }
 }
-impl From<Vec<Self>> for Value {
-    fn from(value: Vec<Value>) -> Self {
-        Self::Array(value)
+impl<T: Into<Self>> From<Option<T>> for Value {
+    /// [`None`] into [`Value::Null`]
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+macro_rules! impl_from_display {
+    ($($ty:ty),+ $(,)?) => {$(
+        impl From<$ty> for Value {
+            fn from(value: $ty) -> Self {
+                Self::String(value.to_string())
+            }
+        }
+    )*};
+}
+impl_from_display! {
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+    f32, f64, bool, char,
+}
+impl<T: Into<Self>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Self::Array(value.into_iter()
+            .map(Into::into)
+            .collect())
     }
 }
-impl From<Vec<&'_ str>> for Value {
-    fn from(value: Vec<&'_ str>) -> Self {
-        value.into_iter().collect()
+impl<T: Clone + Into<Self>> From<&'_ [T]> for Value {
+    fn from(value: &'_ [T]) -> Self {
+        Self::Array(value.iter()
+            .cloned()
+            .map(Into::into)
+            .collect())
     }
 }
 impl<T: Into<Self>, const N: usize> From<[T; N]> for Value {
@@ -161,16 +390,65 @@ impl From<Vec<(String, Self)>> for Value {
         Self::from_iter(value)
     }
 }
+impl From<Tagged> for Value {
+    fn from(value: Tagged) -> Self {
+        Self::Tagged(Box::new(value))
+    }
+}
 impl From<Table> for Value {
     fn from(value: Table) -> Self {
         Self::Table(value)
     }
 }
+impl<K, V, S> From<HashMap<K, V, S>> for Value
+where K: Into<String>, V: Into<Self>,
+{
+    /// Entries are sorted by key for deterministic output
+    fn from(value: HashMap<K, V, S>) -> Self {
+        let mut entries = value.into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect::<Vec<(String, Self)>>();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Self::Table(entries.into_iter().collect())
+    }
+}
+/// With feature `btreemap`, only `BTreeMap<String, Value>` is converted by `From<Table>`
+#[cfg(not(feature = "btreemap"))]
+impl<K, V> From<std::collections::BTreeMap<K, V>> for Value
+where K: Into<String>, V: Into<Self>,
+{
+    fn from(value: std::collections::BTreeMap<K, V>) -> Self {
+        Self::Table(value.into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect())
+    }
+}
+/// Tuples into array, except pairs which are entries of table,
+/// e.g `Value::from([("a", 1)])`
+macro_rules! impl_from_tuple {
+    ($(($($name:ident)+))*) => {$(
+        impl<$($name: Into<Self>),+> From<($($name,)+)> for Value {
+            #[allow(non_snake_case)]
+            fn from(($($name,)+): ($($name,)+)) -> Self {
+                Self::Array(vec![$($name.into()),+])
+            }
+        }
+    )*};
+}
+impl_from_tuple! {
+    (A)
+    (A B C)
+    (A B C D)
+    (A B C D E)
+    (A B C D E F)
+    (A B C D E F G)
+    (A B C D E F G H)
+}
 impl FromIterator<(String, Self)> for Value {
     fn from_iter<T: IntoIterator<Item = (String, Self)>>(iter: T) -> Self {
         let iter = iter.into_iter();
         let mut map
-            = Table::with_capacity(iter.size_hint().0);
+            = table::with_capacity(iter.size_hint().0);
         iter.for_each(|(k, v)| {
             map.entry(k).or_insert(v);
         });
@@ -183,6 +461,63 @@ impl<T1: Into<Self>> FromIterator<T1> for Value {
             .map(Into::into)).into()
     }
 }
+/// Insert entries, existing keys keep their position,
+/// [`Null`] is changed into an empty table
+///
+/// # Panics
+/// If the untagged value is not a table or [`Null`]
+///
+/// [`Null`]: Value::Null
+impl<K: Into<String>, V: Into<Self>> Extend<(K, V)> for Value {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        let value = self.untagged_mut();
+        if value.is_null() {
+            *value = Table::new().into();
+        }
+        match value {
+            Value::Table(table) => iter.into_iter().for_each(|(k, v)| {
+                *table.entry(k.into()).or_insert(Value::Null) = v.into();
+            }),
+            value => panic!("cannot extend {} with entries", value.kind()),
+        }
+    }
+}
+/// Push items, [`Null`] is changed into an empty array
+///
+/// # Panics
+/// If the untagged value is not an array or [`Null`]
+///
+/// [`Null`]: Value::Null
+impl<T1: Into<Self>> Extend<T1> for Value {
+    fn extend<T: IntoIterator<Item = T1>>(&mut self, iter: T) {
+        let value = self.untagged_mut();
+        if value.is_null() {
+            *value = Value::Array(vec![]);
+        }
+        match value {
+            Value::Array(arr) => arr.extend(iter.into_iter().map(Into::into)),
+            value => panic!("cannot extend {} with items", value.kind()),
+        }
+    }
+}
+impl str::FromStr for Value {
+    type Err = ParseError<LineCol>;
+
+    /// Same as [`parser::value`]
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::Value;
+    ///
+    /// let value: Value = "a: [1, 2]".parse().unwrap();
+    /// assert_eq!(value, Value::from([("a", ["1", "2"])]));
+    /// let err = "a: [1".parse::<Value>().unwrap_err();
+    /// assert_eq!((err.location.line, err.location.column), (1, 6));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser::value(s)
+    }
+}
 impl AsRef<Self> for Value {
     fn as_ref(&self) -> &Self {
         self
@@ -218,6 +553,16 @@ impl TryFrom<Value> for Table {
         }
     }
 }
+impl TryFrom<Value> for Tagged {
+    type Error = Value;
+
+    fn try_from(this: Value) -> Result<Tagged, Value> {
+        match this {
+            Value::Tagged(tagged) => Ok(*tagged),
+            oth => Err(oth),
+        }
+    }
+}
 impl<'a> TryFrom<&'a Value> for &'a str {
     type Error = ();
 
@@ -331,3 +676,51 @@ impl_try_from_parse! {
     str::ParseBoolError     = bool;
     char::ParseCharError    = char;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_test() {
+        let tests = [
+            (Value::from(-3i8), "-3"),
+            (Value::from(u128::MAX), "340282366920938463463374607431768211455"),
+            (Value::from(1.5f64), "1.5"),
+            (Value::from(true), "true"),
+            (Value::from('x'), "x"),
+            (Value::from(Some(2u8)), "2"),
+            (Value::from(None::<u8>), "~"),
+            (Value::from(vec![1, 2]), "[1,2]"),
+            (Value::from(&[Some(1), None][..]), "[1,~]"),
+            (Value::from(vec![("a".to_owned(), Value::from(1))]), "{a:1}"),
+            (Value::from(("a", 1, false)), "[a,1,false]"),
+            (Value::from((1,)), "[1]"),
+            (Value::from(HashMap::from([("b", 2), ("a", 1)])), "{a:1,b:2}"),
+            (Value::from(std::collections::BTreeMap::from([("b".to_owned(), Value::Null)])), "{b:~}"),
+            (Value::from([("a", vec![1.5])]), "{a:[1.5]}"),
+        ];
+        for (value, expected) in tests {
+            assert_eq!(value.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn extend_test() {
+        let mut value = Value::Null;
+        value.extend([1, 2]);
+        value.extend(vec!["x"]);
+        assert_eq!(value, Value::from(["1", "2", "x"]));
+
+        let mut value = Value::tagged("t", Value::Null);
+        value.extend([("a", 1)]);
+        value.extend([("b", 2), ("a", 3)]);
+        assert_eq!(value, Value::tagged("t", [("a", "3"), ("b", "2")]));
+    }
+
+    #[test]
+    #[should_panic = "cannot extend string with items"]
+    fn extend_panic_test() {
+        Value::from("x").extend([1]);
+    }
+}
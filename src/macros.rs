@@ -0,0 +1,297 @@
+/// Build [`Value`] by SYML like inline syntax
+///
+/// - `~` is [`Value::Null`]
+/// - Identifiers and literals are strings, e.g `x`, `"a b"`, `80`, `-1.5`,
+///   numbers are written by their values, e.g `1_000u32` is `1000` and `0x10` is `16`
+/// - `(expr)` is interpolated by [`Value::from`]
+/// - `[a, b]` is array, `{k: v}` is table, keys are identifier, literal or `(expr)`
+/// - `!tag value` is tagged value
+///
+/// # Examples
+/// ```
+/// use syml::{syml, parser};
+///
+/// let port = "8080";
+/// let value = syml!({
+///     name: "x y",
+///     ports: [80u16, (port), -1],
+///     (format!("k{}", 1)): !t {a: ~},
+///     -1: 1_000,
+/// });
+/// assert_eq!(value, parser::value("\
+/// name: 'x y'
+/// ports: [80, 8080, -1]
+/// k1: !t {a: ~}
+/// -1: 1000
+/// ").unwrap());
+/// ```
+///
+/// [`Value`]: crate::Value
+/// [`Value::Null`]: crate::Value::Null
+/// [`Value::from`]: crate::Value::from
+#[macro_export]
+macro_rules! syml {
+    (~) => {
+        $crate::Value::Null
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::Value::Array($crate::__syml_array!(@ [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::__syml_table!(@ [] $($tt)*)
+    };
+    (! $tag:ident $($value:tt)+) => {
+        $crate::Value::tagged(stringify!($tag), $crate::syml!($($value)+))
+    };
+    (! $tag:literal $($value:tt)+) => {
+        $crate::Value::tagged($tag, $crate::syml!($($value)+))
+    };
+    (( $value:expr )) => {
+        $crate::Value::from($value)
+    };
+    (- $lit:literal) => {
+        $crate::__private::literal(-$lit)
+    };
+    ($lit:literal) => {
+        $crate::__private::literal($lit)
+    };
+    ($ident:ident) => {
+        $crate::Value::String(stringify!($ident).into())
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __syml_array {
+    (@ [$($elems:expr,)*]) => {
+        ::std::vec![$($elems),*]
+    };
+    (@ [$($elems:expr,)*] $($rest:tt)+) => {
+        $crate::__syml_array!(@elem [$($elems,)*] [] $($rest)+)
+    };
+    (@elem [$($elems:expr,)*] [$($cur:tt)*] , $($rest:tt)*) => {
+        $crate::__syml_array!(@ [$($elems,)* $crate::syml!($($cur)*),] $($rest)*)
+    };
+    (@elem [$($elems:expr,)*] [$($cur:tt)*] $tt:tt $($rest:tt)*) => {
+        $crate::__syml_array!(@elem [$($elems,)*] [$($cur)* $tt] $($rest)*)
+    };
+    (@elem [$($elems:expr,)*] [$($cur:tt)+]) => {
+        $crate::__syml_array!(@ [$($elems,)* $crate::syml!($($cur)+),])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __syml_table {
+    (@ [$(($k:expr, $v:expr),)*]) => {{
+        #[allow(unused_mut)]
+        let mut table = $crate::Table::new();
+        $(
+            table.entry($k).or_insert($v);
+        )*
+        $crate::Value::Table(table)
+    }};
+    (@ [$($entries:tt)*] - $key:literal : $($rest:tt)+) => {
+        $crate::__syml_table!(@val [$($entries)*] ($crate::__syml_key!(- $key)) [] $($rest)+)
+    };
+    (@ [$($entries:tt)*] $key:tt : $($rest:tt)+) => {
+        $crate::__syml_table!(@val [$($entries)*] ($crate::__syml_key!($key)) [] $($rest)+)
+    };
+    (@val [$($entries:tt)*] ($k:expr) [$($cur:tt)*] , $($rest:tt)*) => {
+        $crate::__syml_table!(@ [$($entries)* ($k, $crate::syml!($($cur)*)),] $($rest)*)
+    };
+    (@val [$($entries:tt)*] ($k:expr) [$($cur:tt)*] $tt:tt $($rest:tt)*) => {
+        $crate::__syml_table!(@val [$($entries)*] ($k) [$($cur)* $tt] $($rest)*)
+    };
+    (@val [$($entries:tt)*] ($k:expr) [$($cur:tt)+]) => {
+        $crate::__syml_table!(@ [$($entries)* ($k, $crate::syml!($($cur)+)),])
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __syml_key {
+    (( $key:expr )) => {
+        ::std::string::String::from($key)
+    };
+    ($key:ident) => {
+        ::std::string::String::from(stringify!($key))
+    };
+    (- $key:literal) => {
+        $crate::__private::literal(-$key)
+            .try_into()
+            .unwrap_or_else(|_| ::std::unreachable!())
+    };
+    ($key:literal) => {
+        $crate::__private::literal($key)
+            .try_into()
+            .unwrap_or_else(|_| ::std::unreachable!())
+    };
+}
+
+#[doc(hidden)]
+pub mod private {
+    use crate::{FromSyml, FromSymlError, Kind, Table, Value};
+
+    /// Literal of [`syml!`], numbers are written by their values
+    pub trait Literal {
+        fn into_value(self) -> Value;
+    }
+    impl Literal for &str {
+        fn into_value(self) -> Value {
+            self.into()
+        }
+    }
+    macro_rules! impl_literal_display {
+        ($($ty:ty),+ $(,)?) => {
+            $(
+                impl Literal for $ty {
+                    fn into_value(self) -> Value {
+                        self.to_string().into()
+                    }
+                }
+            )+
+        };
+    }
+    impl_literal_display! {
+        bool, char,
+        i8, i16, i32, i64, i128, isize,
+        u8, u16, u32, u64, u128, usize,
+    }
+    /// Floats keep `.` or exponent, e.g `1.0`
+    macro_rules! impl_literal_float {
+        ($($ty:ty),+ $(,)?) => {
+            $(
+                impl Literal for $ty {
+                    fn into_value(self) -> Value {
+                        format!("{self:?}").into()
+                    }
+                }
+            )+
+        };
+    }
+    impl_literal_float!(f32, f64);
+
+    pub fn literal(value: impl Literal) -> Value {
+        value.into_value()
+    }
+
+    // Support of `#[derive(FromSyml, ToSyml)]`
+
+    pub fn table(value: &Value) -> Result<&Table, FromSymlError> {
+        value.untagged().as_table()
+            .ok_or_else(|| FromSymlError::mismatch(Kind::Table, value))
+    }
+
+    pub fn array(value: &Value, len: usize) -> Result<&[Value], FromSymlError> {
+        match value.untagged() {
+            Value::Array(arr) if arr.len() == len => Ok(arr),
+            Value::Array(arr) => Err(FromSymlError::new(format!(
+                "expected array of length {len}, found length {}",
+                arr.len(),
+            ))),
+            _ => Err(FromSymlError::mismatch(Kind::Array, value)),
+        }
+    }
+
+    pub fn unit(value: &Value) -> Result<(), FromSymlError> {
+        if value.untagged().is_null() {
+            Ok(())
+        } else {
+            Err(FromSymlError::mismatch(Kind::Null, value))
+        }
+    }
+
+    pub fn field<T: FromSyml>(table: &Table, key: &str) -> Result<T, FromSymlError> {
+        match table.get(key) {
+            Some(value) => T::from_syml(value).map_err(|e| e.at(key)),
+            None => T::from_missing().ok_or_else(|| FromSymlError::missing_field(key)),
+        }
+    }
+
+    pub fn field_or<T: FromSyml>(
+        table: &Table,
+        key: &str,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, FromSymlError> {
+        match table.get(key) {
+            Some(value) => T::from_syml(value).map_err(|e| e.at(key)),
+            None => Ok(default()),
+        }
+    }
+
+    /// Entries of table not taken by the named fields, for flattened fields
+    pub fn rest(table: &Table, keys: &[&str]) -> Value {
+        Value::Table(table.iter()
+            .filter(|(k, _)| !keys.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    pub fn element<T: FromSyml>(arr: &[Value], index: usize) -> Result<T, FromSymlError> {
+        T::from_syml(&arr[index]).map_err(|e| e.at(index))
+    }
+
+    pub fn variant<T>(name: &str, variants: &[&str]) -> Result<T, FromSymlError> {
+        Err(FromSymlError::new(format!(
+            "unknown variant `{name}`, expected one of {}",
+            variants.iter()
+                .map(|v| format!("`{v}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )))
+    }
+
+    /// Insert entries of a flattened field, [`Value::Null`] is ignored
+    ///
+    /// # Panics
+    /// - value is not table or null
+    pub fn flatten(table: &mut Table, value: Value) {
+        match value {
+            Value::Table(entries) => {
+                for (k, v) in entries {
+                    table.entry(k).or_insert(v);
+                }
+            },
+            Value::Null => (),
+            value => panic!("flattened field must be table, found {}", value.kind()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser, Value};
+
+    #[test]
+    fn syml_test() {
+        let x = "y";
+        let tests = [
+            (syml!(a), "a"),
+            (syml!(~), "~"),
+            (syml!("~"), "'~'"),
+            (syml!(1.50), "1.5"),
+            (syml!(1e3), "1000.0"),
+            (syml!(-3), "-3"),
+            (syml!(-1.5f32), "-1.5"),
+            (syml!(80u16), "80"),
+            (syml!(1_000), "1000"),
+            (syml!(0x10), "16"),
+            (syml!(true), "true"),
+            (syml!('c'), "c"),
+            (syml!((x)), "y"),
+            (syml!([]), "[]"),
+            (syml!({}), "{}"),
+            (syml!([a, [b, ~], {c: d}, ]), "[a, [b, ~], {c: d}]"),
+            (syml!({a: 1, "b c": [2], 3: !t x, (x): !"u" [y], a: 4}),
+                "{a: 1, 'b c': [2], 3: !t x, y: !u [y]}"),
+            (syml!(!t !u {a: ~}), "!t !u {a: ~}"),
+            (syml!({-1: -2, 1_0: [-3i8]}), "{-1: -2, 10: [-3]}"),
+        ];
+        for (value, src) in tests {
+            let expected = parser::value(src).unwrap_or_else(|e| panic!("{src:?}: {e}"));
+            assert_eq!(value, expected, "{src:?}");
+        }
+        assert_eq!(syml!(!"a b" x), Value::tagged("a b", "x"));
+    }
+}
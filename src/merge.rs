@@ -0,0 +1,241 @@
+use std::mem;
+
+use crate::{table, Table, Value};
+
+/// How [`Value::merge`] merges two arrays
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ArrayMerge {
+    /// Replace by the other array
+    #[default]
+    Replace,
+    /// Append items of the other array
+    Append,
+    /// Merge items of the same index, extra items are appended
+    ByIndex,
+    /// Merge table items with the same string value of the field,
+    /// other items are appended
+    ByKey(String),
+}
+
+/// Options of [`Value::merge`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub arrays: ArrayMerge,
+    /// Table entries with this value are removed instead of merged
+    pub delete: Option<Value>,
+}
+impl MergeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn arrays(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+
+    pub fn delete(mut self, sentinel: impl Into<Value>) -> Self {
+        self.delete = Some(sentinel.into());
+        self
+    }
+
+    fn is_delete(&self, value: &Value) -> bool {
+        self.delete.as_ref() == Some(value)
+    }
+
+    /// Remove the sentinel entries of new value
+    fn strip(&self, value: Value) -> Value {
+        if self.delete.is_none() {
+            return value;
+        }
+        match value {
+            Value::Table(table) => {
+                table.into_iter()
+                    .filter(|(_, v)| !self.is_delete(v))
+                    .map(|(k, v)| (k, self.strip(v)))
+                    .collect()
+            },
+            Value::Array(arr) => {
+                Value::Array(arr.into_iter().map(|v| self.strip(v)).collect())
+            },
+            Value::Tagged(mut tagged) => {
+                tagged.value = self.strip(mem::replace(&mut tagged.value, Value::Null));
+                Value::Tagged(tagged)
+            },
+            _ => value,
+        }
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.untagged().as_table()?.get(key)?.untagged().as_str()
+}
+
+impl Value {
+    /// Merge other value into self, other value takes precedence
+    ///
+    /// - Tables are merged recursively, existing keys keep their position
+    ///   and new keys are appended in order of other table
+    /// - Arrays are merged by [`MergeOptions::arrays`]
+    /// - Values tagged by the same tag merge their inner values
+    /// - Others are replaced, a root delete sentinel replaces self by [`Value::Null`]
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, ArrayMerge, MergeOptions, parser};
+    ///
+    /// let mut config = parser::value("\
+    /// log: info
+    /// db:
+    ///   host: localhost
+    ///   port: 5432
+    /// plugins: [a]
+    /// ").unwrap();
+    /// let site = parser::value("\
+    /// db:
+    ///   port: 6543
+    ///   host: !unset ~
+    /// plugins: [b]
+    /// debug: true
+    /// ").unwrap();
+    /// let options = MergeOptions::new()
+    ///     .arrays(ArrayMerge::Append)
+    ///     .delete(Value::tagged("unset", Value::Null));
+    /// config.merge(site, &options);
+    /// assert_eq!(config, parser::value("\
+    /// log: info
+    /// db:
+    ///   port: 6543
+    /// plugins: [a, b]
+    /// debug: true
+    /// ").unwrap());
+    /// ```
+    pub fn merge(&mut self, other: Value, options: &MergeOptions) {
+        if options.is_delete(&other) {
+            *self = Value::Null;
+            return;
+        }
+        match (self, other) {
+            (Value::Table(table), Value::Table(other)) => {
+                merge_table(table, other, options);
+            },
+            (Value::Array(arr), Value::Array(other)) => {
+                merge_array(arr, other, options);
+            },
+            (Value::Tagged(tagged), Value::Tagged(other)) if tagged.tag == other.tag => {
+                tagged.value.merge(other.value, options);
+            },
+            (this, other) => *this = options.strip(other),
+        }
+    }
+}
+
+fn merge_table(table: &mut Table, other: Table, options: &MergeOptions) {
+    for (k, v) in other {
+        if options.is_delete(&v) {
+            table::remove(table, &k);
+        } else if let Some(this) = table.get_mut(&k) {
+            this.merge(v, options);
+        } else {
+            table.insert(k, options.strip(v));
+        }
+    }
+}
+
+fn merge_array(arr: &mut Vec<Value>, other: Vec<Value>, options: &MergeOptions) {
+    match &options.arrays {
+        ArrayMerge::Replace => {
+            *arr = other.into_iter().map(|v| options.strip(v)).collect();
+        },
+        ArrayMerge::Append => {
+            arr.extend(other.into_iter().map(|v| options.strip(v)));
+        },
+        ArrayMerge::ByIndex => {
+            let mut other = other.into_iter();
+            for (this, v) in arr.iter_mut().zip(other.by_ref()) {
+                this.merge(v, options);
+            }
+            arr.extend(other.map(|v| options.strip(v)));
+        },
+        ArrayMerge::ByKey(key) => {
+            for v in other {
+                let found = field(&v, key).and_then(|name| {
+                    arr.iter().position(|this| field(this, key) == Some(name))
+                });
+                match found {
+                    Some(i) => arr[i].merge(v, options),
+                    None => arr.push(options.strip(v)),
+                }
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    fn merge(base: &str, other: &str, options: &MergeOptions) -> Value {
+        let mut value = parser::value(base).unwrap();
+        value.merge(parser::value(other).unwrap(), options);
+        value
+    }
+
+    #[test]
+    fn merge_test() {
+        let options = MergeOptions::new().delete(Value::tagged("del", Value::Null));
+        let tests = [
+            ("a: 1\nb: 2", "c: 3\na: 4", "a: 4\nb: 2\nc: 3"),
+            ("a:\n  b: 1\n  c: 2", "a:\n  c: 3\n  d: 4", "a:\n  b: 1\n  c: 3\n  d: 4"),
+            ("a: [1, 2]", "a: [3]", "a: [3]"),
+            ("a: {b: 1}", "a: x", "a: x"),
+            ("a: x", "a: {b: 1, c: !del ~}", "a: {b: 1}"),
+            ("a: 1\nb: 2", "a: !del ~\nc: !del ~", "b: 2"),
+            ("a: !t {b: 1}", "a: !t {c: 2}", "a: !t {b: 1, c: 2}"),
+            ("a: !t {b: 1}", "a: !u {c: 2}", "a: !u {c: 2}"),
+            ("a: 1", "a: ~", "a: ~"),
+            ("a: 1", "!del ~", "~"),
+        ];
+        for (base, other, expected) in tests {
+            let expected = parser::value(expected).unwrap();
+            assert_eq!(merge(base, other, &options), expected, "{base:?} {other:?}");
+        }
+    }
+
+    #[test]
+    fn array_merge_test() {
+        let tests = [
+            (ArrayMerge::Append, "[1, 2]", "[3]", "[1, 2, 3]"),
+            (ArrayMerge::ByIndex, "[{a: 1}, 2]", "[{b: 3}, 4, 5]", "[{a: 1, b: 3}, 4, 5]"),
+            (ArrayMerge::ByIndex, "[1, 2]", "[3]", "[3, 2]"),
+            (
+                ArrayMerge::ByKey("name".into()),
+                "[{name: a, x: 1}, {name: b, x: 2}, c]",
+                "[{name: b, x: 3, y: 4}, {name: d}, c]",
+                "[{name: a, x: 1}, {name: b, x: 3, y: 4}, c, {name: d}, c]",
+            ),
+        ];
+        for (arrays, base, other, expected) in tests {
+            let options = MergeOptions::new().arrays(arrays);
+            let expected = parser::value(expected).unwrap();
+            assert_eq!(merge(base, other, &options), expected, "{base:?} {other:?}");
+        }
+    }
+
+    #[test]
+    fn layered_test() {
+        let options = MergeOptions::new().arrays(ArrayMerge::ByKey("name".into()));
+        let mut value = Value::Null;
+        for layer in [
+            "a: 1\nservers:\n- name: x\n  port: 80",
+            "b: 2\nservers:\n- name: y\n  port: 81",
+            "a: 3\nservers:\n- name: x\n  port: 8080",
+        ] {
+            value.merge(parser::value(layer).unwrap(), &options);
+        }
+        assert_eq!(value, parser::value(
+            "a: 3\nservers:\n- name: x\n  port: 8080\n- name: y\n  port: 81\nb: 2"
+        ).unwrap());
+    }
+}
@@ -1,7 +1,57 @@
 pub use parser::*;
+use std::{borrow::Cow, ops::Range};
 use peg::RuleResult;
 
-use crate::Value;
+use crate::{BorrowedValue, TypedValue, Value};
+
+pub mod fast;
+
+/// Key of table and its source span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Key<'a> {
+    pub name: Cow<'a, str>,
+    pub span: Range<usize>,
+}
+
+/// Build the parsed node, the grammar is generic over it
+pub(crate) trait Node<'a>: Sized {
+    /// Unquoted simple literal
+    fn plain(s: &'a str) -> Self;
+    /// `'...'` or `"..."` string
+    fn quoted(s: Cow<'a, str>) -> Self;
+    fn array(arr: Vec<Self>) -> Self;
+    fn table(entries: Vec<(Key<'a>, Self)>) -> Self;
+    fn tagged(tag: &'a str, value: Self) -> Self;
+    /// `~` or empty value of table
+    fn null() -> Self;
+    /// Set the source span of node
+    fn spanned(self, span: Range<usize>) -> Self {
+        let _ = span;
+        self
+    }
+}
+impl<'a> Node<'a> for Value {
+    fn plain(s: &'a str) -> Self {
+        s.into()
+    }
+    fn quoted(s: Cow<'a, str>) -> Self {
+        s.into_owned().into()
+    }
+    fn array(arr: Vec<Self>) -> Self {
+        arr.into()
+    }
+    fn table(entries: Vec<(Key<'a>, Self)>) -> Self {
+        entries.into_iter()
+            .map(|(k, v)| (k.name.into_owned(), v))
+            .collect()
+    }
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        Self::tagged(tag, value)
+    }
+    fn null() -> Self {
+        Self::Null
+    }
+}
 
 trait StrExt {
     fn indent(&self, pos: usize, n: usize) -> RuleResult<()>;
@@ -142,55 +192,111 @@ peg::parser!(grammar parser() for str {
         / s:literal_string() { s.into() }
         / s:string() { s }
 
+    rule string_cow() -> Cow<'input, str>
+        = "\"" s:$(string_ch()*) "\"" { s.into() }
+        / s:string() { s.into() }
 
-    rule inline_list() -> Vec<Value>
-        = "[" _ vals:close_args(<inline_value()>, <_ "," _>)? _ "]"
-        { vals.unwrap_or_default() }
+    rule key() -> Key<'input>
+        = s:position!() name:(
+            s:simple_literal() { s.into() }
+            / s:literal_string() { s.into() }
+            / s:string_cow() { s }
+        ) e:position!()
+        { Key { name, span: s..e } }
 
-    rule inline_table() -> Value
+    rule spanned<N: Node<'input>>(r: rule<N>) -> N
+        = s:position!() v:r() e:position!() { v.spanned(s..e) }
+
+    rule null<N: Node<'input>>() -> N
+        = "~" !simple_literal_continue() { N::null() }
+
+    rule scalar<N: Node<'input>>() -> N
+        = v:null() { v }
+        / s:simple_literal() { N::plain(s) }
+        / s:literal_string() { N::quoted(s.into()) }
+        / s:string_cow() { N::quoted(s) }
+
+
+    rule inline_list<N: Node<'input>>() -> N
+        = "[" _ vals:close_args(<inline_node()>, <_ "," _>)? _ "]"
+        { N::array(vals.unwrap_or_default()) }
+
+    rule inline_table<N: Node<'input>>() -> N
         = "{" _ vals:close_args(<
-            k:simple_val() _ ":" _ v:inline_value() { (k, v) }
+            k:key() _ ":" _ v:inline_node() { (k, v) }
         >, <_ "," _>)? _ "}"
-        { vals.unwrap_or_default().into() }
+        { N::table(vals.unwrap_or_default()) }
+
+    rule tag() -> &'input str
+        = "!" s:simple_literal() { s }
+
+    rule inline_tagged<N: Node<'input>>() -> N
+        = t:tag() [' ' | '\t'] _ v:inline_node()
+        { N::tagged(t, v) }
+
+    rule inline_node<N: Node<'input>>() -> N
+        = spanned(<
+            v:inline_list()     { v }
+            / v:inline_table()  { v }
+            / v:inline_tagged() { v }
+            / v:scalar()        { v }
+        >)
 
     pub rule inline_value() -> Value
-        = v:inline_list()   { v.into() }
-        / v:inline_table()  { v }
-        / v:simple_val()    { v.into() }
+        = v:inline_node() { v }
 
 
-    rule table_val(indent_level: usize) -> Value
-        = cnl() v:(indent(indent_level) v:list(indent_level) { v }
+    rule block_val<N: Node<'input>>(indent_level: usize) -> N
+        = cnl() v:(indent(indent_level) v:spanned(<list(indent_level)>) { v }
             / indent(indent_level+2) v:ivalue_non_inline(indent_level+2) { v }
             ) { v }
-        / _ v:inline_value() { v }
 
-    rule table(indent_level: usize) -> Value
+    rule table_val<N: Node<'input>>(indent_level: usize) -> N
+        = block_val(indent_level)
+        / _ v:spanned(<
+            t:tag() v:block_val(indent_level) { N::tagged(t, v) }
+        >) { v }
+        / _ v:inline_node() { v }
+        / _ v:spanned(<&(comment() / nl()) { N::null() }>) { v }
+
+    rule table<N: Node<'input>>(indent_level: usize) -> N
         = tab:(
-            k:(k:simple_val() _ ":" { k }) ++ _ v:table_val(indent_level)
+            k:(k:key() _ ":" { k }) ++ _ v:table_val(indent_level) e:position!()
             {
                 let mut k = k;
                 let tail = k.pop().unwrap();
                 k.into_iter()
                     .rfold((tail, v), |tab, key| {
-                        (key, [tab].into())
+                        let span = tab.0.span.start..e;
+                        (key, N::table(vec![tab]).spanned(span))
                     })
             }
         ) ++ (cnl() indent(indent_level))
-        { tab.into() }
+        { N::table(tab) }
 
-    rule list(indent_level: usize) -> Value
+    rule list<N: Node<'input>>(indent_level: usize) -> N
         = v:("- " v:ivalue(indent_level+2) {v}) ++ (cnl() indent(indent_level))
-        { v.into() }
+        { N::array(v) }
 
-    rule ivalue_non_inline(indent_level: usize) -> Value
-        = v:(list(indent_level) / table(indent_level))
-        { v }
+    rule ivalue_non_inline<N: Node<'input>>(indent_level: usize) -> N
+        = spanned(<list(indent_level) / table(indent_level)>)
 
-    rule ivalue(indent_level: usize) -> Value
-        = v:(ivalue_non_inline(indent_level) / inline_value())
+    rule ivalue<N: Node<'input>>(indent_level: usize) -> N
+        = v:(ivalue_non_inline(indent_level) / inline_node())
         { v }
 
+    pub(crate) rule node<N: Node<'input>>() -> N
+        = cnl()? v:ivalue(0) cnl() { v }
+
+    pub(crate) rule table_node<N: Node<'input>>() -> N
+        = cnl()? v:spanned(<table(0)>) cnl() { v }
+
+    pub(crate) rule list_node<N: Node<'input>>() -> N
+        = cnl()? v:spanned(<list(0)>) cnl() { v }
+
+    pub(crate) rule empty()
+        = cnl()? eof()
+
 
     /// parse to value node
     ///
@@ -203,7 +309,44 @@ peg::parser!(grammar parser() for str {
     /// assert_eq!(value.unwrap(), expect.into());
     /// ```
     pub rule value() -> Value
-        = cnl()? v:ivalue(0) cnl() { v }
+        = v:node() { v }
+
+    /// parse to typed value node, see [`TypedValue`] for the typing rules
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{TypedValue, parser};
+    ///
+    /// let value = parser::typed_value("[1, '1', 1.5, true, null]");
+    /// let expect = [
+    ///     TypedValue::Int(1),
+    ///     TypedValue::String("1".into()),
+    ///     TypedValue::Float(1.5),
+    ///     TypedValue::Bool(true),
+    ///     TypedValue::Null,
+    /// ];
+    /// assert_eq!(value.unwrap(), expect.into());
+    /// ```
+    pub rule typed_value() -> TypedValue
+        = v:node() { v }
+
+    /// parse to borrowed value node, only escaped string and containers
+    /// are allocated
+    ///
+    /// # Examples
+    /// ```
+    /// use std::borrow::Cow;
+    /// use syml::{BorrowedValue, parser};
+    ///
+    /// let value = parser::borrowed_value("[a, 'b', \"c\", \"\\x64\"]").unwrap();
+    /// let arr = value.as_array().unwrap();
+    /// assert!(matches!(arr[0], BorrowedValue::String(Cow::Borrowed("a"))));
+    /// assert!(matches!(arr[1], BorrowedValue::String(Cow::Borrowed("b"))));
+    /// assert!(matches!(arr[2], BorrowedValue::String(Cow::Borrowed("c"))));
+    /// assert!(matches!(&arr[3], BorrowedValue::String(Cow::Owned(s)) if s == "d"));
+    /// ```
+    pub rule borrowed_value() -> BorrowedValue<'input>
+        = v:node() { v }
 });
 
 #[cfg(test)]
@@ -213,7 +356,7 @@ mod tests {
 
     macro_rules! map {
         ($($k:literal : $v:expr),* $(,)?) => {
-            Value::Table(::linked_hash_map::LinkedHashMap::from_iter(
+            Value::Table(crate::Table::from_iter(
                 [$((String::from($k), Value::from($v))),*]
             ))
         };
@@ -276,9 +419,55 @@ mod tests {
             ("{a:1,}", map!("a": "1")),
             ("{a:1,b:2}", map!("a": "1","b": "2")),
             ("[{a:1,b:2}]", [map!("a": "1","b": "2")].into()),
+            ("~", Value::Null),
+            ("~a", "~a".into()),
+            ("a~", "a~".into()),
+            ("'~'", "~".into()),
+            ("[~, ~]", [Value::Null, Value::Null].into()),
+            ("{a:~}", map!("a": Value::Null)),
+            ("- ~", [Value::Null].into()),
+            ("a:", map!("a": Value::Null)),
+            ("a: ", map!("a": Value::Null)),
+            ("a:\nb: 2", map!("a": Value::Null, "b": "2")),
+            ("a: ; comment\nb:", map!("a": Value::Null, "b": Value::Null)),
+            ("a: b:", map!("a": map!("b": Value::Null))),
+            ("- a:\n  b:\n- ~", [map!("a": Value::Null, "b": Value::Null), Value::Null].into()),
+            ("a: !t ~", map!("a": Value::tagged("t", Value::Null))),
+            ("~: 1", map!("~": "1")),
         ];
         for (src, dst) in tests {
             assert_eq!(parser::value(src), Ok(dst));
         }
     }
+
+    #[test]
+    fn tagged_test() {
+        let tests = [
+            ("!int 5", Value::tagged("int", "5")),
+            ("!int  5", Value::tagged("int", "5")),
+            ("!x", "!x".into()),
+            ("!x\n", "!x".into()),
+            ("!a/b 5s", Value::tagged("a/b", "5s")),
+            ("!a !b c", Value::tagged("a", Value::tagged("b", "c"))),
+            ("!set [a, b]", Value::tagged("set", ["a", "b"])),
+            ("[!x, !int 1 , !x]", ["!x".into(), Value::tagged("int", "1"), "!x".into()].into()),
+            ("{a: !x }", map!("a": "!x")),
+            ("a: !x ;c\nb: 2", map!("a": "!x", "b": "2")),
+            ("a: !int 2", map!("a": Value::tagged("int", "2"))),
+            ("- !int 2", [Value::tagged("int", "2")].into()),
+            ("!x: 2", map!("!x": "2")),
+            ("a: !t\n  b: 2", map!("a": Value::tagged("t", map!("b": "2")))),
+            ("a: !t ; c\n- 2", map!("a": Value::tagged("t", ["2"]))),
+        ];
+        for (src, dst) in tests {
+            assert_eq!(parser::value(src), Ok(dst), "{src:?}");
+        }
+        let fails = [
+            "!x 2: 3",
+            "a: !t\n b: 2",
+        ];
+        for src in fails {
+            assert!(parser::value(src).is_err(), "{src:?}");
+        }
+    }
 }
@@ -0,0 +1,611 @@
+//! Hand-written recursive descent parser
+//!
+//! Accept and reject the same sources as the grammar in [`parser`],
+//! with the same result, but without backtracking on the same prefix.
+//! On reject, the grammar is used to report the error.
+//!
+//! [`parser`]: crate::parser
+
+use std::{borrow::Cow, ops::Range};
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{BorrowedValue, TypedValue, Value};
+use super::{Key, Node};
+
+type Parsed<T> = Option<(usize, T)>;
+
+/// Scalar or key
+enum Token<'a> {
+    Plain(&'a str),
+    Quoted(Cow<'a, str>),
+}
+impl<'a> Token<'a> {
+    fn into_key(self, span: Range<usize>) -> Key<'a> {
+        let name = match self {
+            Token::Plain(s) => s.into(),
+            Token::Quoted(s) => s,
+        };
+        Key { name, span }
+    }
+
+    fn into_node<N: Node<'a>>(self) -> N {
+        match self {
+            Token::Plain("~") => N::null(),
+            Token::Plain(s) => N::plain(s),
+            Token::Quoted(s) => N::quoted(s),
+        }
+    }
+}
+
+fn is_literal_start(ch: u8) -> bool {
+    matches!(ch,
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'(' | b')' | b'*' | b'+'
+        | b'.' | b'/' | b'0'..=b'9' | b'<' | b'=' | b'>' | b'?' | b'@'
+        | b'A'..=b'Z' | b'\\'| b'^' | b'_' | b'`' | b'a'..=b'z' | b'|'
+        | b'~')
+}
+
+fn is_literal_continue(ch: u8) -> bool {
+    is_literal_start(ch) || ch == b'-' || ch == b'\''
+}
+
+fn hex_value(s: &[u8]) -> Option<u32> {
+    let s = std::str::from_utf8(s).ok()?;
+    u32::from_str_radix(s, 16).ok()
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+}
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, bytes: src.as_bytes() }
+    }
+
+    fn at(&self, pos: usize) -> Option<u8> {
+        self.bytes.get(pos).copied()
+    }
+
+    fn char_at(&self, pos: usize) -> Option<char> {
+        self.src[pos..].chars().next()
+    }
+
+    fn starts_with(&self, pos: usize, s: &str) -> bool {
+        self.bytes[pos..].starts_with(s.as_bytes())
+    }
+
+    /// `_`
+    fn ws(&self, mut pos: usize) -> usize {
+        while let Some(b' ' | b'\t') = self.at(pos) {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn nl_noeof(&self, pos: usize) -> Option<usize> {
+        match self.at(pos) {
+            Some(b'\n') => Some(pos+1),
+            Some(b'\r') if self.at(pos+1) == Some(b'\n') => Some(pos+2),
+            _ => None,
+        }
+    }
+
+    fn is_nl(&self, pos: usize) -> bool {
+        pos == self.bytes.len() || self.nl_noeof(pos).is_some()
+    }
+
+    fn comment(&self, pos: usize) -> Option<usize> {
+        if self.at(pos) != Some(b';') {
+            return None;
+        }
+        let mut pos = pos+1;
+        while !self.is_nl(pos) {
+            pos += 1;
+        }
+        Some(pos)
+    }
+
+    fn cnl(&self, mut pos: usize) -> Option<usize> {
+        let mut matched = false;
+        loop {
+            let p = self.ws(pos);
+            let p = self.comment(p).unwrap_or(p);
+            let Some(p) = self.nl_noeof(p) else { break };
+            pos = p;
+            matched = true;
+        }
+        (matched || pos == self.bytes.len()).then_some(pos)
+    }
+
+    fn indent(&self, pos: usize, n: usize) -> Option<usize> {
+        self.bytes[pos..].iter()
+            .take(n)
+            .take_while(|&&ch| ch == b' ')
+            .count()
+            .eq(&n)
+            .then_some(pos+n)
+    }
+
+    fn literal_continue(&self, pos: usize) -> Option<usize> {
+        match self.at(pos)? {
+            ch if ch.is_ascii() => is_literal_continue(ch).then_some(pos+1),
+            _ => {
+                let ch = self.char_at(pos)?;
+                unicode_ident::is_xid_continue(ch)
+                    .then_some(pos+ch.len_utf8())
+            },
+        }
+    }
+
+    fn simple_literal(&self, pos: usize) -> Parsed<&'a str> {
+        let mut end = match self.at(pos)? {
+            b'-' => (self.at(pos+1) != Some(b' ')).then_some(pos+1)?,
+            ch if ch.is_ascii() => is_literal_start(ch).then_some(pos+1)?,
+            _ => {
+                let ch = self.char_at(pos)?;
+                unicode_ident::is_xid_start(ch)
+                    .then_some(pos+ch.len_utf8())?
+            },
+        };
+        while let Some(next) = self.literal_continue(end) {
+            end = next;
+        }
+        Some((end, &self.src[pos..end]))
+    }
+
+    fn literal_string(&self, pos: usize) -> Parsed<&'a str> {
+        if self.at(pos) != Some(b'\'') {
+            return None;
+        }
+        let start = pos+1;
+        let mut end = start;
+        while !self.is_nl(end) && self.bytes[end] != b'\'' {
+            end += 1;
+        }
+        (self.at(end) == Some(b'\'')).then(|| (end+1, &self.src[start..end]))
+    }
+
+    fn string_escaped(&self, pos: usize) -> Parsed<char> {
+        if self.at(pos) != Some(b'\\') {
+            return None;
+        }
+        let hex = |start: usize, n: usize| {
+            let s = self.bytes.get(start..start+n)?;
+            s.iter().all(u8::is_ascii_hexdigit).then_some(start+n)
+        };
+        let code = |start: usize, end: usize| {
+            hex_value(&self.bytes[start..end])
+        };
+        let pos = pos+1;
+        let (end, code) = match self.at(pos)? {
+            ch @ (b'\\' | b'\'' | b'"' | b' ' | b'\t') => {
+                return Some((pos+1, ch.into()));
+            },
+            b'n' => return Some((pos+1, '\n')),
+            b'r' => return Some((pos+1, '\r')),
+            b't' => return Some((pos+1, '\t')),
+            b'x' => {
+                let end = hex(pos+1, 2)?;
+                return Some((end, (code(pos+1, end)? as u8).into()));
+            },
+            b'u' => {
+                if let Some(end) = hex(pos+1, 4) {
+                    (end, code(pos+1, end)?)
+                } else if self.at(pos+1) == Some(b'{') {
+                    let start = pos+2;
+                    let digits = self.bytes[start..].iter()
+                        .take(8)
+                        .take_while(|ch| ch.is_ascii_hexdigit())
+                        .count();
+                    if digits == 0 || self.at(start+digits) != Some(b'}') {
+                        return None;
+                    }
+                    (start+digits+1, code(start, start+digits)?)
+                } else {
+                    return None;
+                }
+            },
+            b'U' => {
+                let end = hex(pos+1, 8)?;
+                (end, code(pos+1, end)?)
+            },
+            _ => return None,
+        };
+        Some((end, char::from_u32(code)?))
+    }
+
+    fn string_ignore_empty(&self, mut pos: usize) -> usize {
+        while self.at(pos) == Some(b'\\') {
+            let p = self.comment(pos+1).unwrap_or(pos+1);
+            let Some(p) = self.nl_noeof(p) else { break };
+            pos = self.ws(p);
+        }
+        pos
+    }
+
+    fn string_ch(&self, pos: usize) -> Parsed<char> {
+        match self.at(pos)? {
+            b'\\' | b'"' => None,
+            _ if self.is_nl(pos) => None,
+            _ => {
+                let ch = self.char_at(pos)?;
+                Some((pos+ch.len_utf8(), ch))
+            },
+        }
+    }
+
+    fn string(&self, pos: usize) -> Parsed<Cow<'a, str>> {
+        if self.at(pos) != Some(b'"') {
+            return None;
+        }
+        let start = pos+1;
+        let mut pos = start;
+        while let Some(b) = self.at(pos) {
+            if b == b'\\' || b == b'"' || self.is_nl(pos) {
+                break;
+            }
+            pos += 1;
+        }
+        if self.at(pos) == Some(b'"') {
+            return Some((pos+1, self.src[start..pos].into()));
+        }
+        let mut s = String::from(&self.src[start..pos]);
+        pos = self.string_ignore_empty(pos);
+        while let Some((p, ch)) = self.string_escaped(pos)
+            .or_else(|| self.string_ch(pos))
+        {
+            s.push(ch);
+            pos = self.string_ignore_empty(p);
+        }
+        (self.at(pos) == Some(b'"')).then(|| (pos+1, s.into()))
+    }
+
+    fn simple_val(&self, pos: usize) -> Parsed<Token<'a>> {
+        match self.at(pos)? {
+            b'\'' => {
+                let (end, s) = self.literal_string(pos)?;
+                Some((end, Token::Quoted(s.into())))
+            },
+            b'"' => {
+                let (end, s) = self.string(pos)?;
+                Some((end, Token::Quoted(s)))
+            },
+            _ => {
+                let (end, s) = self.simple_literal(pos)?;
+                Some((end, Token::Plain(s)))
+            },
+        }
+    }
+
+    fn key(&self, pos: usize) -> Parsed<Key<'a>> {
+        let (end, token) = self.simple_val(pos)?;
+        Some((end, token.into_key(pos..end)))
+    }
+
+    fn scalar<N: Node<'a>>(&self, pos: usize) -> Parsed<N> {
+        let (end, token) = self.simple_val(pos)?;
+        Some((end, token.into_node()))
+    }
+
+    /// `elem ++ sep` and optional tail `sep`
+    fn close_args<T>(
+        &self,
+        pos: usize,
+        elem: impl Fn(usize) -> Parsed<T>,
+    ) -> Parsed<Vec<T>> {
+        let sep = |pos| {
+            let pos = self.ws(pos);
+            (self.at(pos) == Some(b',')).then(|| self.ws(pos+1))
+        };
+        let (mut pos, first) = elem(pos)?;
+        let mut elems = vec![first];
+        while let Some((p, elem)) = sep(pos).and_then(&elem) {
+            elems.push(elem);
+            pos = p;
+        }
+        Some((sep(pos).unwrap_or(pos), elems))
+    }
+
+    fn inline_list<N: Node<'a>>(&self, pos: usize) -> Parsed<N> {
+        let pos = self.ws(pos+1);
+        let (pos, vals) = self.close_args(pos, |pos| self.inline_node(pos))
+            .map_or((pos, vec![]), |(pos, vals)| (pos, vals));
+        let pos = self.ws(pos);
+        (self.at(pos) == Some(b']')).then(|| (pos+1, N::array(vals)))
+    }
+
+    fn inline_table<N: Node<'a>>(&self, pos: usize) -> Parsed<N> {
+        let pos = self.ws(pos+1);
+        let entry = |pos| {
+            let (pos, k) = self.key(pos)?;
+            let pos = self.ws(pos);
+            if self.at(pos) != Some(b':') {
+                return None;
+            }
+            let (pos, v) = self.inline_node(self.ws(pos+1))?;
+            Some((pos, (k, v)))
+        };
+        let (pos, vals) = self.close_args(pos, entry)
+            .map_or((pos, vec![]), |(pos, vals)| (pos, vals));
+        let pos = self.ws(pos);
+        (self.at(pos) == Some(b'}')).then(|| (pos+1, N::table(vals)))
+    }
+
+    fn tag(&self, pos: usize) -> Parsed<&'a str> {
+        if self.at(pos) != Some(b'!') {
+            return None;
+        }
+        self.simple_literal(pos+1)
+    }
+
+    fn inline_tagged<N: Node<'a>>(&self, pos: usize) -> Parsed<N> {
+        let (pos, tag) = self.tag(pos)?;
+        let Some(b' ' | b'\t') = self.at(pos) else { return None };
+        let (pos, v) = self.inline_node(self.ws(pos+1))?;
+        Some((pos, N::tagged(tag, v)))
+    }
+
+    fn inline_node<N: Node<'a>>(&self, pos: usize) -> Parsed<N> {
+        let (end, v) = match self.at(pos)? {
+            b'[' => self.inline_list::<N>(pos)?,
+            b'{' => self.inline_table(pos)?,
+            b'!' => self.inline_tagged(pos)
+                .or_else(|| self.scalar(pos))?,
+            _ => self.scalar(pos)?,
+        };
+        Some((end, v.spanned(pos..end)))
+    }
+
+    fn block_val<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        let pos = self.cnl(pos)?;
+        if let Some((end, v)) = self.indent(pos, level)
+            .and_then(|p| self.list::<N>(p, level).map(|r| (p, r)))
+            .map(|(p, (end, v))| (end, v.spanned(p..end)))
+        {
+            return Some((end, v));
+        }
+        let pos = self.indent(pos, level+2)?;
+        self.ivalue_non_inline(pos, level+2)
+    }
+
+    fn table_val<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        if let Some(res) = self.block_val(pos, level) {
+            return Some(res);
+        }
+        let pos = self.ws(pos);
+        if let Some((end, v)) = self.tag(pos)
+            .and_then(|(p, tag)| {
+                let (end, v) = self.block_val(p, level)?;
+                Some((end, N::tagged(tag, v)))
+            })
+        {
+            return Some((end, v.spanned(pos..end)));
+        }
+        if let Some(res) = self.inline_node(pos) {
+            return Some(res);
+        }
+        (self.comment(pos).is_some() || self.is_nl(pos))
+            .then(|| (pos, N::null().spanned(pos..pos)))
+    }
+
+    /// Table entry, nested keys are folded
+    fn entry<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<(Key<'a>, N)> {
+        let key_colon = |pos| {
+            let (p, k) = self.key(pos)?;
+            let p = self.ws(p);
+            (self.at(p) == Some(b':')).then_some((p+1, k))
+        };
+        let (mut pos, first) = key_colon(pos)?;
+        let mut keys = vec![first];
+        while let Some((p, k)) = key_colon(self.ws(pos)) {
+            keys.push(k);
+            pos = p;
+        }
+        let (end, v) = self.table_val(pos, level)?;
+        let tail = keys.pop().unwrap();
+        let entry = keys.into_iter()
+            .rfold((tail, v), |tab, key| {
+                let span = tab.0.span.start..end;
+                (key, N::table(vec![tab]).spanned(span))
+            });
+        Some((end, entry))
+    }
+
+    /// `elem ++ (cnl() indent(level))`
+    fn block_items<T>(
+        &self,
+        pos: usize,
+        level: usize,
+        elem: impl Fn(usize) -> Parsed<T>,
+    ) -> Parsed<Vec<T>> {
+        let (mut pos, first) = elem(pos)?;
+        let mut elems = vec![first];
+        while let Some((p, elem)) = self.cnl(pos)
+            .and_then(|p| self.indent(p, level))
+            .and_then(&elem)
+        {
+            elems.push(elem);
+            pos = p;
+        }
+        Some((pos, elems))
+    }
+
+    fn table<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        let (end, entries) = self.block_items(pos, level, |pos| {
+            self.entry(pos, level)
+        })?;
+        Some((end, N::table(entries)))
+    }
+
+    fn list<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        let (end, items) = self.block_items(pos, level, |pos| {
+            if !self.starts_with(pos, "- ") {
+                return None;
+            }
+            self.ivalue(pos+2, level+2)
+        })?;
+        Some((end, N::array(items)))
+    }
+
+    fn ivalue_non_inline<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        let (end, v) = self.list::<N>(pos, level)
+            .or_else(|| self.table(pos, level))?;
+        Some((end, v.spanned(pos..end)))
+    }
+
+    fn ivalue<N: Node<'a>>(&self, pos: usize, level: usize) -> Parsed<N> {
+        match self.at(pos)? {
+            b'-' if self.at(pos+1) == Some(b' ') => {
+                let (end, v) = self.list::<N>(pos, level)?;
+                return Some((end, v.spanned(pos..end)));
+            },
+            b'[' | b'{' | b'!' => (),
+            _ => {
+                // avoid parse the scalar twice when it is not a table
+                let (end, token) = self.simple_val(pos)?;
+                let p = self.ws(end);
+                if self.at(p) != Some(b':') {
+                    return Some((end, token.into_node::<N>().spanned(pos..end)));
+                }
+            },
+        }
+        self.ivalue_non_inline(pos, level)
+            .or_else(|| self.inline_node(pos))
+    }
+
+    fn node<N: Node<'a>>(&self) -> Option<N> {
+        let pos = self.cnl(0).unwrap_or(0);
+        let (pos, v) = self.ivalue(pos, 0)?;
+        let pos = self.cnl(pos)?;
+        (pos == self.bytes.len()).then_some(v)
+    }
+}
+
+/// Parse the first key of block table
+pub(crate) fn table_key(src: &str) -> Option<Key<'_>> {
+    let parser = Parser::new(src);
+    let pos = parser.cnl(0).unwrap_or(0);
+    let (end, key) = parser.key(pos)?;
+    let end = parser.ws(end);
+    (parser.at(end) == Some(b':')).then_some(key)
+}
+
+fn parse<'a, N: Node<'a>>(src: &'a str) -> Result<N, ParseError<LineCol>> {
+    match Parser::new(src).node() {
+        Some(v) => Ok(v),
+        None => super::node(src),
+    }
+}
+
+/// Same as [`parser::value`], but use the hand-written parser
+///
+/// # Examples
+/// ```
+/// use syml::{Value, parser};
+///
+/// let value = parser::fast::value("- 1\n- {a:1,b:2}");
+/// let expect = ["1".into(), Value::from([("a", "1"),("b", "2")])];
+/// assert_eq!(value.unwrap(), expect.into());
+/// assert_eq!(parser::fast::value("- 1\n-"), parser::value("- 1\n-"));
+/// ```
+///
+/// [`parser::value`]: crate::parser::value
+pub fn value(src: &str) -> Result<Value, ParseError<LineCol>> {
+    parse(src)
+}
+
+/// Same as [`parser::typed_value`], but use the hand-written parser
+///
+/// [`parser::typed_value`]: crate::parser::typed_value
+pub fn typed_value(src: &str) -> Result<TypedValue, ParseError<LineCol>> {
+    parse(src)
+}
+
+/// Same as [`parser::borrowed_value`], but use the hand-written parser
+///
+/// [`parser::borrowed_value`]: crate::parser::borrowed_value
+pub fn borrowed_value(src: &str) -> Result<BorrowedValue<'_>, ParseError<LineCol>> {
+    parse(src)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{events::Events, parser};
+    use super::*;
+
+    /// Small deterministic generator of SYML-like sources
+    struct Gen(u64);
+    impl Gen {
+        fn next(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn source(&mut self, len: usize) -> String {
+            const PARTS: &[&str] = &[
+                "a", "b", "~", "!t", "1.5", "-", "- ", "-a", ":", ": ",
+                "[", "]", "{", "}", ",", " ", "  ", "\t", "\n", "\r\n",
+                "\r", ";c", "'x'", "'", "\"", "\"y\"", "\"\\n\"", "\\",
+                "\"a\\\n  b\"", "\"\\u{41}\"", "\"\\ud800\"", "\"\\x41\"",
+                "你", "'it''s'", "a:", "- a: 1", "\n  ", "\n    ", "\n- ",
+            ];
+            (0..len)
+                .map(|_| PARTS[self.next(PARTS.len())])
+                .collect()
+        }
+    }
+
+    fn check(src: &str) {
+        let fast = Parser::new(src).node::<Events>();
+        let slow = parser::node::<Events>(src);
+        match (fast, slow) {
+            (Some(fast), Ok(slow)) => assert_eq!(fast.0, slow.0, "{src:?}"),
+            (None, Err(_)) => (),
+            (fast, slow) => {
+                panic!("{src:?}: fast: {:?}, peg: {:?}", fast.map(|e| e.0), slow.map(|e| e.0))
+            },
+        }
+    }
+
+    #[test]
+    fn same_as_grammar_test() {
+        let tests = [
+            "", "a", "a ", "a \n", "a\r", "a\r\n", "- a", "-a", "- - a",
+            "[]", "[ ]", "[a,]", "[a ,b , ]", "[,]", "{}", "{a:1,}", "{a :1}",
+            "~", "~a", "[~,~]", "!t a", "!t  a", "!t", "!t\ta", "!t [a]",
+            "a: 1", "a:1", "a : 1", "a: b: c", "a:\n  b: 1", "a:\n- 1",
+            "a:\n b: 1", "a: !t\n  b: 1", "a: !t ;c\n- 1", "a:", "a: ;c",
+            "a: ]", "- a: 1\n  b: 2\n- c", "- \n", "a: 1\n\n\nb: 2",
+            "'a'", "'a", "'a\nb'", "\"a\\\n;c\n  b\"", "\"\\u{110000}\"",
+            "\"\\u{000041}\"", "\"\\u{123456789}\"", "\"\\U00000041\"",
+            "\"a\\;c\nb\"", "\"\\", "\"\\\\\"", "\"\\\r\n\"", "a\rb",
+            ";c\na", "\n\na\n\n", " a", "a: 1\n a", "- 1\n  - 2",
+        ];
+        for src in tests {
+            check(src);
+        }
+        for entry in fs::read_dir("tests/parse_datas").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "syml") {
+                check(&fs::read_to_string(path).unwrap());
+            }
+        }
+        let mut gen = Gen(0x2545_f491_4f6c_dd1d);
+        for i in 0..50000 {
+            check(&gen.source(i % 24));
+        }
+    }
+
+    #[test]
+    fn error_test() {
+        for src in ["- 1\n-", "a: ]", "[a", "\"\\ud800\""] {
+            assert_eq!(value(src), parser::value(src));
+        }
+    }
+}
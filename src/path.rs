@@ -0,0 +1,584 @@
+//! Path to a node of [`Value`]
+//!
+//! Two text forms are supported:
+//!
+//! - JSON Pointer: `/servers/0/host`, `~1` is `/` and `~0` is `~`
+//! - Dotted: `servers[0].host`, keys can be quoted, e.g `a."b.c"[1]`
+
+use std::{borrow::Cow, error::Error, fmt, mem, str::FromStr};
+
+use crate::{table, Table, Value};
+
+/// A step of [`Path`]
+///
+/// Keys on array are parsed as index, same as JSON Pointer.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+impl Segment {
+    fn array_index(&self) -> Option<usize> {
+        match self {
+            // no sign or leading zeros, same as JSON Pointer
+            Segment::Key(k) => k.parse().ok().filter(|i: &usize| i.to_string() == *k),
+            Segment::Index(i) => Some(*i),
+        }
+    }
+
+    fn get<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match (value.untagged(), self) {
+            (Value::Table(table), Segment::Key(k)) => table.get(k),
+            (Value::Array(arr), _) => arr.get(self.array_index()?),
+            _ => None,
+        }
+    }
+
+    fn get_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        match (value.untagged_mut(), self) {
+            (Value::Table(table), Segment::Key(k)) => table.get_mut(k),
+            (Value::Array(arr), _) => arr.get_mut(self.array_index()?),
+            _ => None,
+        }
+    }
+
+    /// Get or create the child, need [`Segment::can_insert`] checked
+    fn get_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        let value = value.untagged_mut();
+        if value.is_null() {
+            *value = match self {
+                Segment::Key(_) => Table::new().into(),
+                Segment::Index(_) => Value::Array(vec![]),
+            };
+        }
+        match (value, self) {
+            (Value::Table(table), Segment::Key(k)) => {
+                table.entry(k.clone()).or_insert(Value::Null)
+            },
+            (Value::Array(arr), _) => {
+                let i = self.array_index().unwrap();
+                if i == arr.len() {
+                    arr.push(Value::Null);
+                }
+                &mut arr[i]
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Check the child can be got or created,
+    /// `None` value is a [`Value::Null`] will be created
+    fn can_insert<'v>(&self, value: Option<&'v Value>) -> Result<Option<&'v Value>, ()> {
+        match (value.map(Value::untagged), self) {
+            (None | Some(Value::Null), Segment::Key(_)) => Ok(None),
+            (None | Some(Value::Null), Segment::Index(0)) => Ok(None),
+            (Some(Value::Table(table)), Segment::Key(k)) => Ok(table.get(k)),
+            (Some(Value::Array(arr)), _) => {
+                match self.array_index().ok_or(())? {
+                    i if i < arr.len() => Ok(Some(&arr[i])),
+                    i if i == arr.len() => Ok(None),
+                    _ => Err(()),
+                }
+            },
+            _ => Err(()),
+        }
+    }
+}
+impl From<&str> for Segment {
+    fn from(value: &str) -> Self {
+        Self::Key(value.into())
+    }
+}
+impl From<String> for Segment {
+    fn from(value: String) -> Self {
+        Self::Key(value)
+    }
+}
+impl From<usize> for Segment {
+    fn from(value: usize) -> Self {
+        Self::Index(value)
+    }
+}
+
+/// Invalid path text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    /// Byte offset of the error
+    pub offset: usize,
+    pub message: &'static str,
+}
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path at {}: {}", self.offset, self.message)
+    }
+}
+impl Error for PathError { }
+
+/// Path from the root value to a node, empty path is the root
+///
+/// [`Display`] writes the dotted form, use [`to_pointer`] for JSON Pointer.
+///
+/// # Examples
+/// ```
+/// use syml::{Path, Segment};
+///
+/// let path: Path = "servers[0].\"host name\"".parse().unwrap();
+/// assert_eq!(path.segments(), [
+///     Segment::Key("servers".into()),
+///     Segment::Index(0),
+///     Segment::Key("host name".into()),
+/// ]);
+/// assert_eq!(path.to_pointer(), "/servers/0/host name");
+/// assert_eq!(path.to_string(), "servers[0].\"host name\"");
+/// assert_eq!("/a.b/0".parse::<Path>().unwrap().to_string(), "\"a.b\".0");
+/// ```
+///
+/// [`Display`]: fmt::Display
+/// [`to_pointer`]: Path::to_pointer
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path(Vec<Segment>);
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    pub fn into_segments(self) -> Vec<Segment> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the path is root
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, segment: impl Into<Segment>) {
+        self.0.push(segment.into());
+    }
+
+    pub fn pop(&mut self) -> Option<Segment> {
+        self.0.pop()
+    }
+
+    pub(crate) fn push_front(&mut self, segment: impl Into<Segment>) {
+        self.0.insert(0, segment.into());
+    }
+
+    /// Clone and push a segment
+    pub fn join(&self, segment: impl Into<Segment>) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Self(parent.to_vec()))
+    }
+
+    /// Parse JSON Pointer, e.g `/a/0`
+    pub fn parse_pointer(s: &str) -> Result<Self, PathError> {
+        if s.is_empty() {
+            return Ok(Self::new());
+        }
+        let Some(s) = s.strip_prefix('/') else {
+            return Err(PathError { offset: 0, message: "expected `/`" });
+        };
+        let mut path = Self::new();
+        let mut offset = 1;
+        for seg in s.split('/') {
+            let mut key = String::with_capacity(seg.len());
+            let mut rest = seg;
+            while let Some(i) = rest.find('~') {
+                key.push_str(&rest[..i]);
+                key.push(match rest.as_bytes().get(i+1) {
+                    Some(b'0') => '~',
+                    Some(b'1') => '/',
+                    _ => return Err(PathError {
+                        offset: offset + seg.len() - rest.len() + i,
+                        message: "invalid `~` escape",
+                    }),
+                });
+                rest = &rest[i+2..];
+            }
+            key.push_str(rest);
+            path.push(Segment::Key(key));
+            offset += seg.len() + 1;
+        }
+        Ok(path)
+    }
+
+    /// Parse dotted form, e.g `a.b[0]."c.d"`
+    pub fn parse_dotted(s: &str) -> Result<Self, PathError> {
+        let mut path = Self::new();
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let err = |offset, message| Err(PathError { offset, message });
+            match bytes[i] {
+                b'[' => {
+                    let len = bytes[i+1..].iter()
+                        .take_while(|b| b.is_ascii_digit())
+                        .count();
+                    let end = i + 1 + len;
+                    if len == 0 || bytes.get(end) != Some(&b']') {
+                        return err(i, "expected `[index]`");
+                    }
+                    let Ok(index) = s[i+1..end].parse() else {
+                        return err(i + 1, "index too large");
+                    };
+                    path.push(Segment::Index(index));
+                    i = end + 1;
+                    continue;
+                },
+                b'.' if !path.is_empty() => i += 1,
+                _ if path.is_empty() => (),
+                _ => return err(i, "expected `.` or `[`"),
+            }
+            let (key, end) = match bytes.get(i) {
+                Some(b'"') => parse_quoted(s, i)?,
+                _ => {
+                    let len = s[i..].find(is_special).unwrap_or(s.len() - i);
+                    if len == 0 {
+                        return err(i, "expected key");
+                    }
+                    (s[i..i+len].to_owned(), i + len)
+                },
+            };
+            path.push(Segment::Key(key));
+            i = end;
+        }
+        Ok(path)
+    }
+
+    /// Format as JSON Pointer
+    pub fn to_pointer(&self) -> String {
+        let mut s = String::new();
+        for seg in &self.0 {
+            s.push('/');
+            match seg {
+                Segment::Key(k) => s.push_str(&k.replace('~', "~0").replace('/', "~1")),
+                Segment::Index(i) => s.push_str(&i.to_string()),
+            }
+        }
+        s
+    }
+}
+
+fn is_special(ch: char) -> bool {
+    matches!(ch, '.' | '[' | ']' | '"') || ch.is_whitespace()
+}
+
+/// Parse `"..."` at `start`, returns the key and end offset
+fn parse_quoted(s: &str, start: usize) -> Result<(String, usize), PathError> {
+    let mut key = String::new();
+    let mut chars = s[start+1..].char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((key, start + 1 + i + 1)),
+            '\\' => match chars.next() {
+                Some((_, ch @ ('"' | '\\'))) => key.push(ch),
+                _ => return Err(PathError {
+                    offset: start + 1 + i,
+                    message: "invalid escape",
+                }),
+            },
+            ch => key.push(ch),
+        }
+    }
+    Err(PathError { offset: start, message: "unterminated quote" })
+}
+
+/// Parse JSON Pointer if starts with `/` or is empty, otherwise dotted form
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.starts_with('/') {
+            Self::parse_pointer(s)
+        } else {
+            Self::parse_dotted(s)
+        }
+    }
+}
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, seg) in self.0.iter().enumerate() {
+            match seg {
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Key(k) => {
+                    if i != 0 {
+                        f.write_str(".")?;
+                    }
+                    // first key starts with `/` would be parsed as JSON Pointer
+                    if k.is_empty() || k.contains(is_special) || i == 0 && k.starts_with('/') {
+                        f.write_str("\"")?;
+                        for ch in k.chars() {
+                            if matches!(ch, '"' | '\\') {
+                                f.write_str("\\")?;
+                            }
+                            write!(f, "{ch}")?;
+                        }
+                        f.write_str("\"")?;
+                    } else {
+                        f.write_str(k)?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+impl From<Vec<Segment>> for Path {
+    fn from(value: Vec<Segment>) -> Self {
+        Self(value)
+    }
+}
+impl<T: Into<Segment>> FromIterator<T> for Path {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+impl<T: Into<Segment>> Extend<T> for Path {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+mod private {
+    pub trait Sealed { }
+    impl Sealed for super::Path { }
+    impl Sealed for str { }
+    impl Sealed for String { }
+    impl<T: Sealed + ?Sized> Sealed for &T { }
+}
+
+/// [`Path`] or its text form parsed by [`FromStr`], used by [`Value::require`] and others
+pub trait AsPath: private::Sealed {
+    #[doc(hidden)]
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError>;
+}
+impl AsPath for Path {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+impl AsPath for str {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        self.parse().map(Cow::Owned)
+    }
+}
+impl AsPath for String {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        self.as_str().as_path()
+    }
+}
+impl<T: AsPath + ?Sized> AsPath for &T {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        (**self).as_path()
+    }
+}
+
+impl Value {
+    /// Get the node at path, tags are skipped
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let value = parser::value("a:\n- b: x").unwrap();
+    /// let path = "/a/0/b".parse().unwrap();
+    /// assert_eq!(value.pointer(&path), Some(&Value::from("x")));
+    /// assert_eq!(value.pointer(&"a[0].c".parse().unwrap()), None);
+    /// ```
+    pub fn pointer(&self, path: &Path) -> Option<&Self> {
+        path.0.iter().try_fold(self, |value, seg| seg.get(value))
+    }
+
+    pub fn pointer_mut(&mut self, path: &Path) -> Option<&mut Self> {
+        path.0.iter().try_fold(self, |value, seg| seg.get_mut(value))
+    }
+
+    /// Insert value at path, returns the old value
+    ///
+    /// Missing tables are created, [`Value::Null`] is changed into a table,
+    /// index equal to array length pushes the value.
+    ///
+    /// Nothing is changed and the value is returned as `Err`,
+    /// if the path can not be created.
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::{Value, parser};
+    ///
+    /// let mut value = parser::value("a: [x]").unwrap();
+    /// value.insert_at(&"a[1]".parse().unwrap(), "y").unwrap();
+    /// value.insert_at(&"b.c".parse().unwrap(), "z").unwrap();
+    /// assert_eq!(value, parser::value("a: [x, y]\nb:\n  c: z").unwrap());
+    /// assert!(value.insert_at(&"a.x".parse().unwrap(), "w").is_err());
+    /// ```
+    pub fn insert_at(
+        &mut self,
+        path: &Path,
+        value: impl Into<Self>,
+    ) -> Result<Option<Self>, Self> {
+        let value = value.into();
+        let mut node = Some(&*self);
+        for seg in &path.0 {
+            match seg.can_insert(node) {
+                Ok(next) => node = next,
+                Err(()) => return Err(value),
+            }
+        }
+        let existed = node.is_some();
+        let node = path.0.iter()
+            .fold(self, |node, seg| seg.get_or_insert(node));
+        let old = mem::replace(node, value);
+        Ok(existed.then_some(old))
+    }
+
+    /// Remove the node at path, returns the removed value
+    ///
+    /// Remove root is not allowed and returns [`None`]
+    pub fn remove_at(&mut self, path: &Path) -> Option<Self> {
+        let (last, parent) = path.0.split_last()?;
+        let parent = parent.iter()
+            .try_fold(self, |value, seg| seg.get_mut(value))?;
+        match (parent.untagged_mut(), last) {
+            (Value::Table(table), Segment::Key(k)) => table::remove(table, k),
+            (Value::Array(arr), _) => {
+                let i = last.array_index()?;
+                (i < arr.len()).then(|| arr.remove(i))
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        use Segment::*;
+        let tests = [
+            ("", vec![]),
+            ("/", vec![Key("".into())]),
+            ("/a/0", vec![Key("a".into()), Key("0".into())]),
+            ("/a~0b~1c/~01", vec![Key("a~b/c".into()), Key("~1".into())]),
+            ("/é/x", vec![Key("é".into()), Key("x".into())]),
+            ("a", vec![Key("a".into())]),
+            ("[1][2]", vec![Index(1), Index(2)]),
+            ("a.b[0].c", vec![Key("a".into()), Key("b".into()), Index(0), Key("c".into())]),
+            ("a.\"b.c d\"", vec![Key("a".into()), Key("b.c d".into())]),
+            ("\"\".\"\\\"\\\\\"", vec![Key("".into()), Key("\"\\".into())]),
+            ("a.0", vec![Key("a".into()), Key("0".into())]),
+        ];
+        for (src, expected) in tests {
+            let path = src.parse::<Path>().unwrap();
+            assert_eq!(path.segments(), expected, "{src:?}");
+            assert_eq!(path.to_string().parse::<Path>().unwrap(), path, "{src:?}");
+            assert_eq!(Path::parse_pointer(&path.to_pointer()).unwrap(), Path(
+                path.0.iter().map(|seg| match seg {
+                    Index(i) => Key(i.to_string()),
+                    key => key.clone(),
+                }).collect()
+            ), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn display_round_trip_test() {
+        use Segment::*;
+        let tests = [
+            (vec![Key("/a".into())], "\"/a\""),
+            (vec![Key("/".into()), Key("/b".into())], "\"/\"./b"),
+            (vec![Key("a".into()), Key("/b".into())], "a./b"),
+            (vec![Index(0), Key("/c".into())], "[0]./c"),
+        ];
+        for (segments, expected) in tests {
+            let path = Path::from(segments);
+            assert_eq!(path.to_string(), expected);
+            assert_eq!(path.to_string().parse::<Path>().unwrap(), path, "{expected:?}");
+        }
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let tests = [
+            ("/a~2", 2),
+            ("/é/~", 4),
+            ("a.", 2),
+            ("a..b", 2),
+            ("a[", 1),
+            ("a[x]", 1),
+            ("a b", 1),
+            ("a.\"b", 2),
+            ("a.\"\\n\"", 3),
+            ("[0]a", 3),
+            (".a", 0),
+        ];
+        for (src, offset) in tests {
+            let e = src.parse::<Path>().unwrap_err();
+            assert_eq!(e.offset, offset, "{src:?}: {e}");
+        }
+    }
+
+    #[test]
+    fn pointer_test() {
+        let mut value = parser::value("a: !t\n  b: [x, {c: y}]\n\"1\": z").unwrap();
+        let get = |value: &Value, path: &str| value.pointer(&path.parse().unwrap()).cloned();
+        assert_eq!(get(&value, ""), Some(value.clone()));
+        assert_eq!(get(&value, "/a/b/1/c"), Some("y".into()));
+        assert_eq!(get(&value, "a.b[1].c"), Some("y".into()));
+        assert_eq!(get(&value, "a.b.0"), Some("x".into()));
+        assert_eq!(get(&value, "a.b.+0"), None);
+        assert_eq!(get(&value, "/a/b/01/c"), None);
+        assert_eq!(get(&value, "/a/b/00"), None);
+        assert_eq!(get(&value, "a.b[2]"), None);
+        assert_eq!(get(&value, "/1"), Some("z".into()));
+        assert_eq!(get(&value, "[1]"), None);
+
+        let path = "a.b[0]".parse().unwrap();
+        *value.pointer_mut(&path).unwrap() = "w".into();
+        assert_eq!(value.pointer(&path), Some(&"w".into()));
+    }
+
+    #[test]
+    fn insert_remove_test() {
+        let mut value = parser::value("a: [x]\nb: s\nc: ~").unwrap();
+        let mut insert = |path: &str, v: &str| {
+            value.insert_at(&path.parse().unwrap(), v)
+        };
+        assert_eq!(insert("a[0]", "x1"), Ok(Some("x".into())));
+        assert_eq!(insert("a[1].d", "y"), Ok(None));
+        assert_eq!(insert("a.2", "z"), Ok(None));
+        assert_eq!(insert("c.d[0]", "w"), Ok(None));
+        assert_eq!(insert("e.f.g", "v"), Ok(None));
+        assert_eq!(insert("a[4]", "_"), Err("_".into()));
+        assert_eq!(insert("a.x", "_"), Err("_".into()));
+        assert_eq!(insert("b.x", "_"), Err("_".into()));
+        assert_eq!(insert("f[1]", "_"), Err("_".into()));
+        assert_eq!(value, parser::value(concat!(
+            "a: [x1, {d: y}, z]\nb: s\n",
+            "c:\n  d: [w]\ne:\n  f:\n    g: v",
+        )).unwrap());
+
+        let mut remove = |path: &str| value.remove_at(&path.parse().unwrap());
+        assert_eq!(remove("a[1].d"), Some("y".into()));
+        assert_eq!(remove("a[0]"), Some("x1".into()));
+        assert_eq!(remove("a[5]"), None);
+        assert_eq!(remove("/e"), Some([("f", [("g", "v")])].into()));
+        assert_eq!(remove(""), None);
+        assert_eq!(value, parser::value("a: [{}, z]\nb: s\nc:\n  d: [w]").unwrap());
+    }
+}
@@ -0,0 +1,466 @@
+//! JSONPath like query over [`Value`]
+//!
+//! Syntax:
+//!
+//! | Step              | Selects                                          |
+//! | ----------------- | ------------------------------------------------ |
+//! | `$`               | the root, optional                               |
+//! | `.key` `."key"`   | table value, first step can omit the `.`         |
+//! | `.*` `[*]`        | all table values or array items                  |
+//! | `..step`          | apply step on the node and all its descendants   |
+//! | `[1]` `[-1]`      | array item, negative index counts from the end   |
+//! | `['a', "b", 0]`   | union of keys and indices                        |
+//! | `[start:end:step]`| array slice, same as Python                      |
+//! | `[?(expr)]`       | table values or array items matching the filter  |
+//!
+//! Filter expression compare string values, `@` is the current node:
+//! `@.a == 'x'`, `!=`, `^=` (starts with), `$=` (ends with), `*=` (contains),
+//! bare `@.a` checks existence, combined by `!`, `&&`, `||` and parentheses.
+//!
+//! Tags are skipped while selecting.
+//!
+//! # Examples
+//! ```
+//! use syml::{Value, parser, query::Query};
+//!
+//! let value = parser::value("\
+//! services:
+//!   api:
+//!     image: nginx
+//!     ports: [80, 443]
+//!   db:
+//!     image: postgres
+//! ").unwrap();
+//! let query: Query = "services.*.image".parse().unwrap();
+//! let images = query.select(&value).into_iter()
+//!     .map(|m| (m.path.to_string(), m.value.as_str().unwrap()))
+//!     .collect::<Vec<_>>();
+//! assert_eq!(images, [
+//!     ("services.api.image".into(), "nginx"),
+//!     ("services.db.image".into(), "postgres"),
+//! ]);
+//!
+//! let matches = value.query("$..[?(@.image ^= 'post')]").unwrap();
+//! assert_eq!(matches[0].path.to_string(), "services.db");
+//! assert_eq!(value.query("..ports[-1]").unwrap()[0].value, &Value::from("443"));
+//! ```
+
+use std::str::FromStr;
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{Path, Segment, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Key(String),
+    Index(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    /// Relative path from `@`
+    Node(Vec<Item>),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Or(Box<Self>, Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Not(Box<Self>),
+    Cmp(Operand, CmpOp, Operand),
+    Exists(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    Wildcard,
+    Union(Vec<Item>),
+    Slice(Option<i64>, Option<i64>, i64),
+    Filter(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    /// Apply on all descendants, the `..`
+    descend: bool,
+    selector: Selector,
+}
+
+peg::parser!(grammar query_parser() for str {
+    rule _()
+        = quiet! { [' ' | '\t']* }
+
+    rule name() -> String
+        = quiet! { s:$((![
+            '.' | '[' | ']' | '(' | ')' | '"' | '\'' | '*' | '?' | '@'
+            | '$' | '=' | '!' | '^' | '&' | '|' | ',' | ':'
+        ] !(c:[_] {? if c.is_whitespace() { Ok(()) } else { Err("") }}) [_])+)
+        { s.into() } }
+        / expected!("name")
+
+    rule quoted() -> String
+        = "\"" s:(
+            "\\" c:['"' | '\'' | '\\'] { c }
+            / !['"' | '\\'] c:[_] { c }
+        )* "\"" { s.into_iter().collect() }
+        / "'" s:(
+            "\\" c:['"' | '\'' | '\\'] { c }
+            / !['\'' | '\\'] c:[_] { c }
+        )* "'" { s.into_iter().collect() }
+
+    rule int() -> i64
+        = s:$("-"? ['0'..='9']+) {? s.parse().or(Err("integer")) }
+
+    rule item() -> Item
+        = k:quoted() { Item::Key(k) }
+        / i:int() { Item::Index(i) }
+
+    rule dot_selector() -> Selector
+        = "*" { Selector::Wildcard }
+        / k:(name() / quoted()) { Selector::Union(vec![Item::Key(k)]) }
+
+    rule bracket_selector() -> Selector
+        = "[" _ s:(
+            "*" { Selector::Wildcard }
+            / "?" _ e:expr() { Selector::Filter(e) }
+            / start:int()? _ ":" _ end:int()? step:(_ ":" _ s:int()? { s })?
+            {?
+                match step.flatten() {
+                    Some(0) => Err("non-zero step"),
+                    step => Ok(Selector::Slice(start, end, step.unwrap_or(1))),
+                }
+            }
+            / items:(item() ++ (_ "," _)) { Selector::Union(items) }
+        ) _ "]" { s }
+
+    rule step() -> Step
+        = ".." s:(dot_selector() / bracket_selector())
+        { Step { descend: true, selector: s } }
+        / "." s:dot_selector() { Step { descend: false, selector: s } }
+        / s:bracket_selector() { Step { descend: false, selector: s } }
+
+    rule operand() -> Operand
+        = "@" items:(
+            "." k:(name() / quoted()) { Item::Key(k) }
+            / "[" _ i:item() _ "]" { i }
+        )* { Operand::Node(items) }
+        / s:quoted() { Operand::Literal(s) }
+
+    rule cmp_op() -> CmpOp
+        = "==" { CmpOp::Eq }
+        / "!=" { CmpOp::Ne }
+        / "^=" { CmpOp::StartsWith }
+        / "$=" { CmpOp::EndsWith }
+        / "*=" { CmpOp::Contains }
+
+    rule expr() -> Expr = precedence! {
+        a:(@) _ "||" _ b:@ { Expr::Or(Box::new(a), Box::new(b)) }
+        --
+        a:(@) _ "&&" _ b:@ { Expr::And(Box::new(a), Box::new(b)) }
+        --
+        "!" _ e:@ { Expr::Not(Box::new(e)) }
+        --
+        a:operand() _ op:cmp_op() _ b:operand() { Expr::Cmp(a, op, b) }
+        a:operand() { Expr::Exists(a) }
+        "(" _ e:expr() _ ")" { e }
+    }
+
+    pub rule query() -> Vec<Step>
+        = _ first:(
+            "$" { None }
+            / s:dot_selector() { Some(Step { descend: false, selector: s }) }
+        )? steps:step()* _
+        {
+            first.flatten().into_iter().chain(steps).collect()
+        }
+});
+
+/// A selected node and its path from the root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub path: Path,
+    pub value: &'a Value,
+}
+
+/// Parsed query, see [module level documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+impl Query {
+    pub fn parse(s: &str) -> Result<Self, ParseError<LineCol>> {
+        Ok(Self { steps: query_parser::query(s)? })
+    }
+
+    /// Select matched nodes in document order of each step
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<Match<'a>> {
+        let mut matches = vec![Match { path: Path::new(), value }];
+        for step in &self.steps {
+            let mut next = vec![];
+            for m in matches {
+                if step.descend {
+                    let mut nodes = vec![];
+                    descendants(m, &mut nodes);
+                    for node in nodes {
+                        step.selector.apply(node, &mut next);
+                    }
+                } else {
+                    step.selector.apply(m, &mut next);
+                }
+            }
+            matches = next;
+        }
+        matches
+    }
+}
+impl FromStr for Query {
+    type Err = ParseError<LineCol>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// The node and all its descendants, in pre-order
+fn descendants<'a>(m: Match<'a>, out: &mut Vec<Match<'a>>) {
+    let mut children = vec![];
+    push_children(&m, &mut children);
+    out.push(m);
+    for child in children {
+        descendants(child, out);
+    }
+}
+
+fn push_children<'a>(m: &Match<'a>, out: &mut Vec<Match<'a>>) {
+    match m.value.untagged() {
+        Value::Table(table) => out.extend(table.iter().map(|(k, v)| Match {
+            path: m.path.join(k.as_str()),
+            value: v,
+        })),
+        Value::Array(arr) => out.extend(arr.iter().enumerate().map(|(i, v)| Match {
+            path: m.path.join(i),
+            value: v,
+        })),
+        _ => (),
+    }
+}
+
+fn array_index(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+impl Item {
+    /// Get child, key on array is parsed as index
+    fn get<'a>(&self, value: &'a Value) -> Option<(Segment, &'a Value)> {
+        match (value.untagged(), self) {
+            (Value::Table(table), Item::Key(k)) => {
+                Some((Segment::Key(k.clone()), table.get(k)?))
+            },
+            (Value::Array(arr), Item::Key(k)) => {
+                let i = k.parse().ok().filter(|_| !k.starts_with('+'))?;
+                Some((Segment::Index(i), arr.get(i)?))
+            },
+            (Value::Array(arr), &Item::Index(i)) => {
+                let i = array_index(arr.len(), i)?;
+                Some((Segment::Index(i), &arr[i]))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Selector {
+    fn apply<'a>(&self, m: Match<'a>, out: &mut Vec<Match<'a>>) {
+        match self {
+            Selector::Wildcard => push_children(&m, out),
+            Selector::Union(items) => {
+                out.extend(items.iter().filter_map(|item| {
+                    let (seg, value) = item.get(m.value)?;
+                    Some(Match { path: m.path.join(seg), value })
+                }));
+            },
+            &Selector::Slice(start, end, step) => {
+                let Value::Array(arr) = m.value.untagged() else { return };
+                out.extend(slice(arr.len(), start, end, step).map(|i| Match {
+                    path: m.path.join(i),
+                    value: &arr[i],
+                }));
+            },
+            Selector::Filter(expr) => {
+                let mut children = vec![];
+                push_children(&m, &mut children);
+                out.extend(children.into_iter().filter(|m| expr.eval(m.value)));
+            },
+        }
+    }
+}
+
+/// Indices of Python like slice, `step` is non-zero
+fn slice(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: i64,
+) -> impl Iterator<Item = usize> {
+    let len = len as i64;
+    let norm = |i: i64| if i < 0 { i + len } else { i };
+    let (start, end) = if step > 0 {
+        (start.map_or(0, norm).clamp(0, len), end.map_or(len, norm).clamp(0, len))
+    } else {
+        (
+            start.map_or(len - 1, norm).clamp(-1, len - 1),
+            end.map_or(-1, |end| norm(end).clamp(-1, len - 1)),
+        )
+    };
+    let mut i = Some(start);
+    std::iter::from_fn(move || {
+        let cur = i?;
+        let in_range = if step > 0 { cur < end } else { cur > end };
+        in_range.then(|| {
+            i = cur.checked_add(step);
+            cur as usize
+        })
+    })
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, value: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Node(items) => {
+                items.iter().try_fold(value, |value, item| {
+                    Some(item.get(value)?.1)
+                })
+            },
+            Operand::Literal(_) => None,
+        }
+    }
+
+    fn as_str<'a>(&'a self, value: &'a Value) -> Option<&'a str> {
+        match self {
+            Operand::Node(_) => self.resolve(value)?.untagged().as_str(),
+            Operand::Literal(s) => Some(s),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, value: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(value) || b.eval(value),
+            Expr::And(a, b) => a.eval(value) && b.eval(value),
+            Expr::Not(e) => !e.eval(value),
+            Expr::Exists(Operand::Literal(_)) => true,
+            Expr::Exists(operand) => operand.resolve(value).is_some(),
+            Expr::Cmp(a, op, b) => {
+                let (Some(a), Some(b)) = (a.as_str(value), b.as_str(value)) else {
+                    return false;
+                };
+                match op {
+                    CmpOp::Eq => a == b,
+                    CmpOp::Ne => a != b,
+                    CmpOp::StartsWith => a.starts_with(b),
+                    CmpOp::EndsWith => a.ends_with(b),
+                    CmpOp::Contains => a.contains(b),
+                }
+            },
+        }
+    }
+}
+
+impl Value {
+    /// Parse query and select matched nodes, see [`Query`]
+    pub fn query(&self, query: &str) -> Result<Vec<Match<'_>>, ParseError<LineCol>> {
+        Ok(Query::parse(query)?.select(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    const SRC: &str = "\
+a:
+  b: [0, 1, 2, 3, 4]
+  c: !t
+    d: x
+'e f':
+- d: y
+  g: yes
+- d: z
+";
+
+    fn select(query: &str) -> Vec<String> {
+        let value = parser::value(SRC).unwrap();
+        let result = value.query(query).unwrap();
+        for m in &result {
+            assert_eq!(value.pointer(&m.path), Some(m.value), "{query:?}");
+        }
+        result.into_iter().map(|m| m.path.to_string()).collect()
+    }
+
+    #[test]
+    fn select_test() {
+        let tests: &[(&str, &[&str])] = &[
+            ("", &[""]),
+            ("$", &[""]),
+            ("a", &["a"]),
+            ("$.a.c.d", &["a.c.d"]),
+            ("a.x", &[]),
+            ("'e f'[0].d", &["\"e f\"[0].d"]),
+            ("$[\"e f\", 'a']", &["\"e f\"", "a"]),
+            ("*", &["a", "\"e f\""]),
+            ("a.*", &["a.b", "a.c"]),
+            ("a.b[*]", &["a.b[0]", "a.b[1]", "a.b[2]", "a.b[3]", "a.b[4]"]),
+            ("a.b.1", &["a.b[1]"]),
+            ("a.b[-1, 0, 5]", &["a.b[4]", "a.b[0]"]),
+            ("a.b[1:3]", &["a.b[1]", "a.b[2]"]),
+            ("a.b[::2]", &["a.b[0]", "a.b[2]", "a.b[4]"]),
+            ("a.b[-2:]", &["a.b[3]", "a.b[4]"]),
+            ("a.b[::-2]", &["a.b[4]", "a.b[2]", "a.b[0]"]),
+            ("a.b[3:0:-1]", &["a.b[3]", "a.b[2]", "a.b[1]"]),
+            ("a.b[9:]", &[]),
+            ("a.b[1::9223372036854775807]", &["a.b[1]"]),
+            ("a.b[::-9223372036854775808]", &["a.b[4]"]),
+            ("..d", &["a.c.d", "\"e f\"[0].d", "\"e f\"[1].d"]),
+            ("$..[1]", &["a.b[1]", "\"e f\"[1]"]),
+            ("..*", &[
+                "a", "\"e f\"", "a.b", "a.c",
+                "a.b[0]", "a.b[1]", "a.b[2]", "a.b[3]", "a.b[4]", "a.c.d",
+                "\"e f\"[0]", "\"e f\"[1]",
+                "\"e f\"[0].d", "\"e f\"[0].g", "\"e f\"[1].d",
+            ]),
+            ("..[?(@.d == 'x')]", &["a.c"]),
+            ("..[?(@.d)]", &["a.c", "\"e f\"[0]", "\"e f\"[1]"]),
+            ("'e f'[?(@.d != 'y')]", &["\"e f\"[1]"]),
+            ("..[?@.g]", &["\"e f\"[0]"]),
+            ("..[?!@.g && @.d]", &["a.c", "\"e f\"[1]"]),
+            ("..[?(@.d ^= 'y' || @.d $= 'z')].d", &["\"e f\"[0].d", "\"e f\"[1].d"]),
+            ("a.b[?(@ *= '3')]", &["a.b[3]"]),
+            ("a.b[?('1' == @)]", &["a.b[1]"]),
+            ("a[?(@[2] == '2')]", &["a.b"]),
+        ];
+        for &(query, expected) in tests {
+            assert_eq!(select(query), expected, "{query:?}");
+        }
+    }
+
+    #[test]
+    fn parse_error_test() {
+        for query in ["a..", "a.", "[", "[1:2:0]", "a b", "[?(@.a ==)]", "[?(@ = 'a')]", "$$"] {
+            assert!(Query::parse(query).is_err(), "{query:?}");
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Serde support of [`Value`] by the `serde_impl` feature
+//!
+//! Values are mapped to the serde data model directly,
+//! [`Value::Null`] is none, so it fits `Option<T>` and JSON `null`.
+//! Tags are dropped when serializing, like converting into JSON,
+//! and duplicate keys keep the first, like the parser.
+//!
+//! Before 0.5.0, [`Value`] was the externally tagged enum of serde derive.
+
+use std::fmt;
+
+use serde::{
+    de::{self, MapAccess, SeqAccess},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{table, Value};
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for item in arr {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            },
+            Value::Table(table) => {
+                let mut map = serializer.serialize_map(Some(table.len()))?;
+                for (k, v) in table {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            },
+            Value::Tagged(tagged) => tagged.value.serialize(serializer),
+            Value::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Accept any self-describing data, other scalars are converted into strings
+struct Visitor;
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a syml value")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            arr.push(item);
+        }
+        Ok(Value::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut table = table::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((k, v)) = map.next_entry()? {
+            table.entry(k).or_insert(v);
+        }
+        Ok(Value::Table(table))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::{value::{Error, MapDeserializer, SeqDeserializer}, IntoDeserializer};
+
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn deserialize_test() {
+        let null = Value::deserialize(().into_deserializer());
+        assert_eq!(null, Ok::<_, Error>(Value::Null));
+        let none = Option::<Value>::deserialize(().into_deserializer());
+        assert_eq!(none, Ok::<_, Error>(None));
+        let num = Value::deserialize(IntoDeserializer::<Error>::into_deserializer(-1i64));
+        assert_eq!(num, Ok("-1".into()));
+        let arr = Value::deserialize(SeqDeserializer::<_, Error>::new(["a", "b"].into_iter()));
+        assert_eq!(arr, Ok(["a", "b"].into()));
+        let table = Value::deserialize(MapDeserializer::<_, Error>::new([("a", "1"), ("a", "2")].into_iter()));
+        assert_eq!(table, Ok(parser::value("a: 1\na: 2").unwrap()));
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn serde_json_test() {
+        let value = parser::value("a: ~\nb: [x, !t y]\nc:").unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"a":null,"b":["x","y"],"c":null}"#);
+        let back: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, parser::value("a: ~\nb: [x, y]\nc:").unwrap());
+
+        let opt: Option<Value> = serde_json::from_str("null").unwrap();
+        assert_eq!(opt, None);
+        let opt: Vec<Option<Value>> = serde_json::from_str(r#"[null, "x"]"#).unwrap();
+        assert_eq!(opt, [None, Some("x".into())]);
+    }
+}
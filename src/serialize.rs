@@ -1,24 +1,93 @@
-use std::fmt::{Arguments, Write};
+use std::fmt::{self, Arguments, Write};
 
-use linked_hash_map::LinkedHashMap;
+use crate::{Table, Tagged, Value};
 
-use crate::Value;
+fn do_indent<F: FnMut(Arguments<'_>)>(f: &mut F, indent: usize) {
+    f(format_args!("{:indent$}", ""));
+}
 
-macro_rules! match_variant {
-    ($ty:tt => $e:expr => {
-        $var:ident : $(|)? $($variant:ident)|+ => $do:expr
-    }) => {
-        match $e {
-            $(
-                $ty::$variant($var) => $do,
-            )+
-        }
-    };
+fn is_inline(value: &Value) -> bool {
+    value.is_string() || value.is_tagged() || value.is_empty()
 }
 
-fn do_indent<F: FnMut(Arguments<'_>)>(f: &mut F, indent: usize) {
-    for _ in 0..indent {
-        f(format_args!("  "));
+/// Quotes of strings which are not literals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QuoteStyle {
+    /// `'a b'`, or double quotes if the string has to be escaped
+    #[default]
+    Single,
+    /// `"a b"`
+    Double,
+}
+
+/// Options of [`Value::serialize_with`]
+///
+/// The default is the same as [`SYMLSerialize::serialize`],
+/// nested blocks are always indented by 2 spaces as the grammar requires.
+///
+/// # Examples
+/// ```
+/// use syml::{QuoteStyle, SerializeOptions, parser};
+///
+/// let value = parser::value("a: [1, 2]\nb: {c: 'x y', d: [3], e: ~}\nf: g").unwrap();
+/// let options = SerializeOptions::new()
+///     .inline_items(2)
+///     .quote(QuoteStyle::Double);
+/// assert_eq!(value.to_string_with(&options), "\
+/// a: [1, 2]
+/// b:
+///   c: \"x y\"
+///   d: [3]
+///   e: ~
+/// f: g");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SerializeOptions {
+    /// Arrays and tables with at most this many items are written inline,
+    /// default 0, use [`usize::MAX`] to inline by [`max_width`] only
+    ///
+    /// [`max_width`]: SerializeOptions::max_width
+    pub inline_items: usize,
+    /// Inlined arrays and tables never make a line wider than this, default 80
+    pub max_width: usize,
+    pub quote: QuoteStyle,
+    /// Quote strings that look like numbers or bools, e.g `'1'` and `'true'`
+    pub quote_ambiguous: bool,
+}
+const DEFAULT_OPTIONS: SerializeOptions = SerializeOptions {
+    inline_items: 0,
+    max_width: 80,
+    quote: QuoteStyle::Single,
+    quote_ambiguous: false,
+};
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        DEFAULT_OPTIONS
+    }
+}
+impl SerializeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inline_items(mut self, n: usize) -> Self {
+        self.inline_items = n;
+        self
+    }
+
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = width;
+        self
+    }
+
+    pub fn quote(mut self, quote: QuoteStyle) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn quote_ambiguous(mut self, yes: bool) -> Self {
+        self.quote_ambiguous = yes;
+        self
     }
 }
 
@@ -112,115 +181,271 @@ pub trait SYMLSerialize {
 }
 impl SYMLSerialize for Value {
     fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
-        match_variant!(Value => self => {
-            v: String | Array | Table => v.serialize(f, indent)
-        })
+        block(self, f, indent*2, &DEFAULT_OPTIONS)
     }
     fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
-        match_variant!(Value => self => {
-            v: String | Array | Table => v.serialize_min(f)
-        })
+        inline(self, f, &DEFAULT_OPTIONS, false)
+    }
+}
+impl SYMLSerialize for Tagged {
+    fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
+        inline_tagged(self, f, &DEFAULT_OPTIONS, false)
     }
 }
 impl SYMLSerialize for String {
     fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
-        if self.is_empty() {
-            return f(format_args!("''"));
-        }
-        if crate::parser::simple_literal(self).is_ok() {
-            return f(format_args!("{self}"));
-        }
-        if self.chars()
-            .all(|ch| ch != '\''
-                && (ch == '"' || ch.escape_debug().size_hint().0 == 1))
-        {
-            return f(format_args!("'{self}'"));
-        }
-        f(format_args!("\""));
-        for ch in self.chars() {
-            match ch {
-                '\n' => f(format_args!("\\n")),
-                '\r' => f(format_args!("\\r")),
-                '\t' => f(format_args!("\\t")),
-                '\"' => f(format_args!("\\\"")),
-                '\'' => f(format_args!("'")),
-                '\\' => f(format_args!("\\\\")),
-                '\0'..='\x1f' | '\x7f' => f(format_args!("\\x{:02x}", ch as u32)),
-                _ => match ch.escape_debug().size_hint().0 {
-                    1 => f(format_args!("{ch}")),
-                    2 => unreachable!("unescaped char: {:?}", ch),
-                    _ if u8::try_from(ch).is_ok() => {
-                        f(format_args!("\\x{:02x}", ch as u8))
-                    },
-                    _ if u16::try_from(ch).is_ok() => {
-                        f(format_args!("\\u{:04x}", ch as u16))
-                    },
-                    _  => f(format_args!("\\u{{{:01x}}}", ch as u32)),
-                },
-            }
-        }
-        f(format_args!("\""));
+        string(self, f, &DEFAULT_OPTIONS, true)
     }
 }
 impl SYMLSerialize for [Value] {
     fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
-        f(format_args!("["));
-        match self {
-            [] => (),
-            [head, next @ ..] => {
-                head.serialize_min(f);
-                for val in next {
-                    f(format_args!(","));
-                    val.serialize_min(f)
-                }
+        inline_array(self, f, &DEFAULT_OPTIONS, false)
+    }
+    fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
+        block_array(self, f, indent*2, &DEFAULT_OPTIONS)
+    }
+}
+impl SYMLSerialize for Table {
+    fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
+        inline_table(self, f, &DEFAULT_OPTIONS, false)
+    }
+    fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
+        block_table(self, f, indent*2, &DEFAULT_OPTIONS)
+    }
+}
+
+fn is_ambiguous(s: &str) -> bool {
+    s.parse::<f64>().is_ok() || s.parse::<bool>().is_ok()
+}
+
+/// Write string as literal or quoted, `is_value` is false for keys
+fn string<F: FnMut(Arguments<'_>)>(s: &str, f: &mut F, o: &SerializeOptions, is_value: bool) {
+    let literal = !s.is_empty() && s != "~"
+        && !(is_value && o.quote_ambiguous && is_ambiguous(s))
+        && crate::parser::simple_literal(s).is_ok();
+    if literal {
+        return f(format_args!("{s}"));
+    }
+    if o.quote == QuoteStyle::Single
+        && s.chars()
+            .all(|ch| ch != '\''
+                && (ch == '"' || ch.escape_debug().size_hint().0 == 1))
+    {
+        return f(format_args!("'{s}'"));
+    }
+    f(format_args!("\""));
+    for ch in s.chars() {
+        match ch {
+            '\n' => f(format_args!("\\n")),
+            '\r' => f(format_args!("\\r")),
+            '\t' => f(format_args!("\\t")),
+            '\"' => f(format_args!("\\\"")),
+            '\'' => f(format_args!("'")),
+            '\\' => f(format_args!("\\\\")),
+            '\0'..='\x1f' | '\x7f' => f(format_args!("\\x{:02x}", ch as u32)),
+            _ => match ch.escape_debug().size_hint().0 {
+                1 => f(format_args!("{ch}")),
+                2 => unreachable!("unescaped char: {:?}", ch),
+                _ if u8::try_from(ch).is_ok() => {
+                    f(format_args!("\\x{:02x}", ch as u8))
+                },
+                _ if u16::try_from(ch).is_ok() => {
+                    f(format_args!("\\u{:04x}", ch as u16))
+                },
+                _  => f(format_args!("\\u{{{:01x}}}", ch as u32)),
             },
         }
-        f(format_args!("]"));
     }
-    fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
-        if self.is_empty() { return self.serialize_min(f); }
+    f(format_args!("\""));
+}
+
+/// Inline form, `spaced` adds spaces after `,` and `:`
+fn inline<F: FnMut(Arguments<'_>)>(value: &Value, f: &mut F, o: &SerializeOptions, spaced: bool) {
+    match value {
+        Value::String(s) => string(s, f, o, true),
+        Value::Array(arr) => inline_array(arr, f, o, spaced),
+        Value::Table(table) => inline_table(table, f, o, spaced),
+        Value::Tagged(tagged) => inline_tagged(tagged, f, o, spaced),
+        Value::Null => f(format_args!("~")),
+    }
+}
+
+fn inline_tagged<F: FnMut(Arguments<'_>)>(tagged: &Tagged, f: &mut F, o: &SerializeOptions, spaced: bool) {
+    f(format_args!("!{} ", tagged.tag));
+    inline(&tagged.value, f, o, spaced);
+}
+
+fn inline_array<F: FnMut(Arguments<'_>)>(arr: &[Value], f: &mut F, o: &SerializeOptions, spaced: bool) {
+    let sep = if spaced { ", " } else { "," };
+    f(format_args!("["));
+    arr.iter().fold(true, |head, val| {
+        if !head { f(format_args!("{sep}")); }
+        inline(val, f, o, spaced);
+        false
+    });
+    f(format_args!("]"));
+}
+
+fn inline_table<F: FnMut(Arguments<'_>)>(table: &Table, f: &mut F, o: &SerializeOptions, spaced: bool) {
+    let (sep, colon) = if spaced { (", ", ": ") } else { (",", ":") };
+    f(format_args!("{{"));
+    table.iter().fold(true, |head, (k, v)| {
+        if !head { f(format_args!("{sep}")); }
+        string(k, f, o, false);
+        f(format_args!("{colon}"));
+        inline(v, f, o, spaced);
+        false
+    });
+    f(format_args!("}}"));
+}
+
+fn collect(serialize: impl FnOnce(&mut dyn FnMut(Arguments<'_>))) -> String {
+    let mut buf = String::new();
+    serialize(&mut |args| write!(buf, "{args}").unwrap());
+    buf
+}
+
+/// Spaced inline form of array or table, if inlined by the options at column `col`
+fn inline_form(value: &Value, o: &SerializeOptions, col: usize) -> Option<String> {
+    let len = match value {
+        Value::Array(arr) => arr.len(),
+        Value::Table(table) => table.len(),
+        _ => return None,
+    };
+    if len > o.inline_items {
+        return None;
+    }
+    let buf = collect(|out| inline(value, &mut |args| out(args), o, true));
+    (col + buf.chars().count() <= o.max_width).then_some(buf)
+}
+
+/// Block form, `indent` is in spaces
+fn block<F: FnMut(Arguments<'_>)>(value: &Value, f: &mut F, indent: usize, o: &SerializeOptions) {
+    match value {
+        Value::Array(arr) => block_array(arr, f, indent, o),
+        Value::Table(table) => block_table(table, f, indent, o),
+        _ => inline(value, f, o, false),
+    }
+}
+
+/// Inline form if inlined by the options, or block form
+fn block_or_inline<F: FnMut(Arguments<'_>)>(value: &Value, f: &mut F, indent: usize, o: &SerializeOptions) {
+    match inline_form(value, o, indent) {
+        Some(s) => f(format_args!("{s}")),
+        None => block(value, f, indent, o),
+    }
+}
+
+fn block_array<F: FnMut(Arguments<'_>)>(arr: &[Value], f: &mut F, indent: usize, o: &SerializeOptions) {
+    if arr.is_empty() { return inline_array(arr, f, o, false); }
+    f(format_args!("- "));
+    block_or_inline(&arr[0], f, indent+2, o);
+    for val in &arr[1..] {
+        f(format_args!("\n"));
+        do_indent(f, indent);
         f(format_args!("- "));
-        self[0].serialize(f, indent+1);
-        for val in &self[1..] {
+        block_or_inline(val, f, indent+2, o);
+    }
+}
+
+fn block_table<F: FnMut(Arguments<'_>)>(table: &Table, f: &mut F, indent: usize, o: &SerializeOptions) {
+    if table.is_empty() { return inline_table(table, f, o, false); }
+    table.iter().fold(true, |head, (k, v)| {
+        if !head {
             f(format_args!("\n"));
             do_indent(f, indent);
-            f(format_args!("- "));
-            val.serialize(f, indent+1);
         }
-    }
+        let mut col = indent + 2;
+        if o.inline_items != 0 {
+            col += collect(|out| string(k, &mut |args| out(args), o, false)).chars().count();
+        }
+        string(k, f, o, false);
+        f(format_args!(":"));
+        let v = match v {
+            Value::Tagged(tagged) if !is_inline(&tagged.value) => {
+                f(format_args!(" !{}", tagged.tag));
+                col += tagged.tag.chars().count() + 2;
+                &tagged.value
+            },
+            _ => v,
+        };
+        if is_inline(v) {
+            f(format_args!(" "));
+            inline(v, f, o, false);
+        } else if let Some(s) = inline_form(v, o, col) {
+            f(format_args!(" {s}"));
+        } else {
+            let inc = if !v.is_array() { 2 } else { 0 };
+            f(format_args!("\n"));
+            do_indent(f, indent+inc);
+            block(v, f, indent+inc, o);
+        }
+        false
+    });
 }
-impl SYMLSerialize for LinkedHashMap<String, Value> {
-    fn serialize_min<F: FnMut(Arguments<'_>)>(&self, f: &mut F) {
-        f(format_args!("{{"));
-        self.iter().fold(true, |head, (k, v)| {
-            if !head { f(format_args!(",")); }
-            k.serialize_min(f);
-            f(format_args!(":"));
-            v.serialize_min(f);
-            false
-        });
-        f(format_args!("}}"));
+
+impl Value {
+    /// Serialize to block form by options,
+    /// `indent` is the level added to the lines after the first, same as [`SYMLSerialize::serialize`]
+    pub fn serialize_with<F: FnMut(Arguments<'_>)>(
+        &self,
+        f: &mut F,
+        indent: usize,
+        options: &SerializeOptions,
+    ) {
+        block_or_inline(self, f, indent*2, options)
     }
-    fn serialize<F: FnMut(Arguments<'_>)>(&self, f: &mut F, indent: usize) {
-        if self.is_empty() { return self.serialize_min(f); }
-        self.iter().fold(true, |head, (k, v)| {
-            if !head {
-                f(format_args!("\n"));
-                do_indent(f, indent);
-            }
-            k.serialize_min(f);
-            f(format_args!(":"));
-            if v.is_string() || v.is_empty() {
-                f(format_args!(" "));
-                v.serialize_min(f);
-            } else {
-                let inc = if !v.is_array() { 1 } else { 0 };
-                f(format_args!("\n"));
-                do_indent(f, indent+inc);
-                v.serialize(f, indent+inc);
-            }
-            false
-        });
+
+    /// Same as [`serialize_with`], but collect to string
+    ///
+    /// [`serialize_with`]: Value::serialize_with
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        collect(|out| self.serialize_with(&mut |args| out(args), 0, options))
+    }
+}
+
+/// Adapt [`fmt::Formatter`] to the callback of [`SYMLSerialize`]
+fn write_fmt(
+    f: &mut fmt::Formatter<'_>,
+    serialize: impl FnOnce(&mut dyn FnMut(Arguments<'_>)),
+) -> fmt::Result {
+    let mut result = Ok(());
+    serialize(&mut |args| {
+        if result.is_ok() {
+            result = f.write_fmt(args);
+        }
+    });
+    result
+}
+
+impl fmt::Display for Value {
+    /// `{}` writes the compact form, `{:#}` writes the block form
+    ///
+    /// In block form, width is the indent in spaces of all lines,
+    /// precision is the indent level added to the lines after the first,
+    /// same as the indent of [`SYMLSerialize::serialize`].
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::parser;
+    ///
+    /// let value = parser::value("a: {b: [1, 2]}").unwrap();
+    /// assert_eq!(format!("{value}"), "{a:{b:[1,2]}}");
+    /// assert_eq!(format!("{value:#}"), "a:\n  b:\n  - 1\n  - 2");
+    /// assert_eq!(format!("x:\n{value:#4}"), "x:\n    a:\n      b:\n      - 1\n      - 2");
+    /// assert_eq!(format!("- {value:#.1}"), "- a:\n    b:\n    - 1\n    - 2");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return write_fmt(f, |out| self.serialize_min(&mut |args| out(args)));
+        }
+        let base = f.width().unwrap_or(0);
+        let indent = base + f.precision().unwrap_or(0) * 2;
+        write_fmt(f, |out| {
+            let mut out = |args: Arguments<'_>| out(args);
+            do_indent(&mut out, base);
+            block(self, &mut out, indent, &DEFAULT_OPTIONS)
+        })
     }
 }
 
@@ -246,6 +471,8 @@ mod tests {
             ("'a\u{10ffff}'", r#""a\u{10ffff}""#),
             ("{a:1,b:2,c:[3,4]}", "{a:1,b:2,c:[3,4]}"),
             ("{名字:小明}", "{名字:小明}"),
+            ("!t [!u a,{b:!v ''}]", "!t [!u a,{b:!v ''}]"),
+            ("[~,'~',~a,{a:~}]", "[~,'~',~a,{a:~}]"),
         ];
         for (src, dst) in tests {
             let val = parser::value(src).unwrap();
@@ -263,7 +490,7 @@ mod tests {
             ("{a:1,b:{x:1,y:2}}", "a: 1\nb:\n  x: 1\n  y: 2"),
             ("{l:[[1,2],[3,4]]}", "l:\n- - 1\n  - 2\n- - 3\n  - 4"),
             ("{l:[[1,[2, 3]],[3,[4,5]]]}", "l:\n- - 1\n  - - 2\n    - 3\n- - 3\n  - - 4\n    - 5"),
-            ("{a:1,b:{x:1,y:{n:2,i:3}},c:3}", "a: 1\nb:\n  x: 1\n  y:\n    n: 2\n    i: 3\nc: 3"),
+            ("{a:1,b:{x:1,y:{i:2,n:3}},c:3}", "a: 1\nb:\n  x: 1\n  y:\n    i: 2\n    n: 3\nc: 3"),
             ("{}", "{}"),
             ("[]", "[]"),
             ("234", "234"),
@@ -271,6 +498,11 @@ mod tests {
             ("[[],{},'']", "- []\n- {}\n- ''"),
             ("[[],{a:1},'']", "- []\n- a: 1\n- ''"),
             ("[[],{a:1,b:2},'']", "- []\n- a: 1\n  b: 2\n- ''"),
+            ("{a:!t 1,b:!t []}", "a: !t 1\nb: !t []"),
+            ("{a:!t {x:1},b:!t [1]}", "a: !t\n  x: 1\nb: !t\n- 1"),
+            ("[!t {x:1},!t 2]", "- !t {x:1}\n- !t 2"),
+            ("a:\nb: !t ~\nc:\n- ~", "a: ~\nb: !t ~\nc:\n- ~"),
+            ("~", "~"),
         ];
         for (src, dst) in tests {
             let val = parser::value(src).unwrap();
@@ -304,4 +536,75 @@ mod tests {
             assert_eq!(s, dst);
         }
     }
+
+    #[test]
+    fn display_test() {
+        let src = "a:\n  b:\n  - - 1\n    - x: 2\n      y: {}\n  c: !t\n    d: ''\ne: []";
+        let val: Value = src.parse().unwrap();
+        assert_eq!(format!("{val:#}"), src);
+        assert_eq!(val.to_string(), val.serialize_min_to_string());
+        assert_eq!(format!("{val:#.1}"), val.serialize_to_string(1));
+        assert_eq!(format!("{:#}", Value::from("a b")), "'a b'");
+        assert_eq!(format!("{:#3}", Value::from(["x"])), "   - x");
+
+        let nested = format!("k:\n{val:#2}\nl:\n- {val:#.1}\nm: {val}");
+        let nested: Value = nested.parse().unwrap();
+        assert_eq!(nested, Value::from([("k", val.clone()), ("l", [val.clone()].into()), ("m", val)]));
+    }
+
+    #[test]
+    fn options_test() {
+        let src = "\
+a: {b: [1, 2], c: {d: 'x y', e: [[3, 4]]}}
+f: [{g: true}, [5, 6, 7], ~]
+h: !t {i: ''}
+";
+        let val: Value = src.parse().unwrap();
+        let tests = [
+            (SerializeOptions::new(), val.serialize_to_string(0)),
+            (SerializeOptions::new().inline_items(1), "\
+a:
+  b:
+  - 1
+  - 2
+  c:
+    d: 'x y'
+    e: [[3, 4]]
+f:
+- {g: true}
+- - 5
+  - 6
+  - 7
+- ~
+h: !t {i: ''}".into()),
+            (SerializeOptions::new().inline_items(usize::MAX).max_width(20), "\
+a:
+  b: [1, 2]
+  c:
+    d: 'x y'
+    e: [[3, 4]]
+f:
+- {g: true}
+- [5, 6, 7]
+- ~
+h: !t {i: ''}".into()),
+            (SerializeOptions::new().inline_items(usize::MAX), "\
+a: {b: [1, 2], c: {d: 'x y', e: [[3, 4]]}}
+f: [{g: true}, [5, 6, 7], ~]
+h: !t {i: ''}".into()),
+            (SerializeOptions::new().inline_items(usize::MAX).max_width(100), "\
+{a: {b: [1, 2], c: {d: 'x y', e: [[3, 4]]}}, f: [{g: true}, [5, 6, 7], ~], h: !t {i: ''}}".into()),
+            (SerializeOptions::new().inline_items(3).quote(QuoteStyle::Double).quote_ambiguous(true), "\
+a: {b: [\"1\", \"2\"], c: {d: \"x y\", e: [[\"3\", \"4\"]]}}
+f: [{g: \"true\"}, [\"5\", \"6\", \"7\"], ~]
+h: !t {i: \"\"}".into()),
+        ];
+        for (options, expected) in tests {
+            let out = val.to_string_with(&options);
+            assert_eq!(out, expected, "{options:?}");
+            assert_eq!(out.parse::<Value>(), Ok(val.clone()), "{options:?}");
+        }
+        let options = SerializeOptions::new().quote(QuoteStyle::Double).quote_ambiguous(true);
+        assert_eq!(Value::from([("'", "a'b"), ("1", "1.5")]).to_string_with(&options), "\"'\": a'b\n1: \"1.5\"");
+    }
 }
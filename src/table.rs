@@ -0,0 +1,112 @@
+use std::hash::{Hash, Hasher};
+
+use crate::Value;
+
+/// Table of [`Value`], the backend is selected by features
+///
+/// - default: [`LinkedHashMap`], in insertion order
+/// - `indexmap`: [`IndexMap`], in insertion order
+/// - `btreemap`: [`BTreeMap`], sorted by key, takes precedence over `indexmap`
+///
+/// The features are not additive, code relying on one backend,
+/// like [`LinkedHashMap::entry`] or the position after `insert` of an existing key,
+/// may break when another crate enables a different one.
+/// Equality ignores the order except on the default backend.
+///
+/// Other backends can be converted from and into by [`FromIterator`] and [`IntoIterator`],
+/// or [`Value::from`].
+///
+/// [`LinkedHashMap`]: linked_hash_map::LinkedHashMap
+/// [`LinkedHashMap::entry`]: linked_hash_map::LinkedHashMap::entry
+/// [`IndexMap`]: https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html
+/// [`BTreeMap`]: std::collections::BTreeMap
+#[cfg(not(any(feature = "indexmap", feature = "btreemap")))]
+pub type Table = linked_hash_map::LinkedHashMap<String, Value>;
+#[cfg(all(feature = "indexmap", not(feature = "btreemap")))]
+pub type Table = indexmap::IndexMap<String, Value>;
+#[cfg(feature = "btreemap")]
+pub type Table = std::collections::BTreeMap<String, Value>;
+
+#[cfg(any(feature = "indexmap", feature = "btreemap"))]
+impl From<linked_hash_map::LinkedHashMap<String, Value>> for Value {
+    fn from(value: linked_hash_map::LinkedHashMap<String, Value>) -> Self {
+        Self::Table(value.into_iter().collect())
+    }
+}
+#[cfg(all(feature = "indexmap", feature = "btreemap"))]
+impl From<indexmap::IndexMap<String, Value>> for Value {
+    fn from(value: indexmap::IndexMap<String, Value>) -> Self {
+        Self::Table(value.into_iter().collect())
+    }
+}
+
+/// Create table, capacity is ignored by `btreemap`
+#[cfg(not(feature = "btreemap"))]
+pub(crate) fn with_capacity(capacity: usize) -> Table {
+    Table::with_capacity(capacity)
+}
+#[cfg(feature = "btreemap")]
+pub(crate) fn with_capacity(_capacity: usize) -> Table {
+    Table::new()
+}
+
+/// Remove entry and keep the order of others
+#[cfg(not(all(feature = "indexmap", not(feature = "btreemap"))))]
+pub(crate) fn remove(table: &mut Table, key: &str) -> Option<Value> {
+    table.remove(key)
+}
+#[cfg(all(feature = "indexmap", not(feature = "btreemap")))]
+pub(crate) fn remove(table: &mut Table, key: &str) -> Option<Value> {
+    table.shift_remove(key)
+}
+
+#[cfg(not(all(feature = "indexmap", not(feature = "btreemap"))))]
+pub(crate) fn hash<H: Hasher>(table: &Table, state: &mut H) {
+    table.hash(state)
+}
+
+/// `IndexMap` equality ignores the order, so does the hash
+#[cfg(all(feature = "indexmap", not(feature = "btreemap")))]
+pub(crate) fn hash<H: Hasher>(table: &Table, state: &mut H) {
+    use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+
+    let entries = table.iter()
+        .map(|entry| BuildHasherDefault::<DefaultHasher>::default().hash_one(entry))
+        .fold(0u64, u64::wrapping_add);
+    table.len().hash(state);
+    entries.hash(state);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{hash_map::DefaultHasher, BTreeMap};
+
+    use linked_hash_map::LinkedHashMap;
+
+    use crate::parser;
+    use super::*;
+
+    fn hash_of(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn backend_test() {
+        let value = parser::value("b: [1, {d: 2, c: 3}]\na: ~").unwrap();
+        let table = value.as_table().unwrap().clone();
+
+        let sorted: BTreeMap<String, Value> = table.clone().into_iter().collect();
+        assert_eq!(sorted.keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(Value::from(sorted).eq_unordered(&value));
+
+        let linked: LinkedHashMap<String, Value> = table.into_iter().collect();
+        assert_eq!(Value::from(linked), value);
+
+        let mut other = value.clone();
+        remove(other.as_table_mut().unwrap(), "b");
+        assert_eq!(other, parser::value("a: ~").unwrap());
+        assert_eq!(hash_of(&value), hash_of(&value.clone()));
+    }
+}
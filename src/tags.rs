@@ -0,0 +1,139 @@
+//! Handlers for tagged values, e.g `!int 5`
+
+use std::{collections::HashMap, error::Error, fmt};
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{parser, Tagged, Value};
+
+type Handler = dyn Fn(Value) -> Result<Value, String>;
+
+/// Handler failed on a tagged value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagError {
+    pub tag: String,
+    pub message: String,
+}
+impl fmt::Display for TagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid value for tag `!{}`: {}", self.tag, self.message)
+    }
+}
+impl Error for TagError { }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagParseError {
+    Syntax(ParseError<LineCol>),
+    Tag(TagError),
+}
+impl fmt::Display for TagParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagParseError::Syntax(e) => e.fmt(f),
+            TagParseError::Tag(e) => e.fmt(f),
+        }
+    }
+}
+impl Error for TagParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TagParseError::Syntax(e) => Some(e),
+            TagParseError::Tag(e) => Some(e),
+        }
+    }
+}
+impl From<ParseError<LineCol>> for TagParseError {
+    fn from(value: ParseError<LineCol>) -> Self {
+        Self::Syntax(value)
+    }
+}
+impl From<TagError> for TagParseError {
+    fn from(value: TagError) -> Self {
+        Self::Tag(value)
+    }
+}
+
+/// Registered handlers of tags
+///
+/// The handler receives the value after the tag,
+/// and the returned value replaces the whole tagged value.
+/// Tags without handler are kept as is.
+///
+/// # Examples
+/// ```
+/// use syml::{Value, tags::TagRegistry};
+///
+/// let mut tags = TagRegistry::new();
+/// tags.register("int", |value| {
+///     let s = value.as_str().ok_or("expected string")?;
+///     s.parse::<i64>().map_err(|e| e.to_string())?;
+///     Ok(value)
+/// });
+///
+/// let value = tags.parse("{a: !int 5, b: !date 2026-01-01}").unwrap();
+/// assert_eq!(value, Value::from([
+///     ("a", Value::from("5")),
+///     ("b", Value::tagged("date", "2026-01-01")),
+/// ]));
+/// assert!(tags.parse("!int x").is_err());
+/// ```
+#[derive(Default)]
+pub struct TagRegistry {
+    handlers: HashMap<String, Box<Handler>>,
+}
+impl fmt::Debug for TagRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.handlers.keys())
+            .finish()
+    }
+}
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register handler for tag, replace the old handler
+    pub fn register<F>(&mut self, tag: impl Into<String>, handler: F) -> &mut Self
+    where F: Fn(Value) -> Result<Value, String> + 'static,
+    {
+        self.handlers.insert(tag.into(), Box::new(handler));
+        self
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.handlers.contains_key(tag)
+    }
+
+    /// Apply handlers to all tagged values, inner value first
+    pub fn apply(&self, value: Value) -> Result<Value, TagError> {
+        Ok(match value {
+            Value::String(_) | Value::Null => value,
+            Value::Array(arr) => {
+                arr.into_iter()
+                    .map(|val| self.apply(val))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            },
+            Value::Table(table) => {
+                table.into_iter()
+                    .map(|(k, v)| Ok((k, self.apply(v)?)))
+                    .collect::<Result<Value, _>>()?
+            },
+            Value::Tagged(tagged) => {
+                let Tagged { tag, value } = *tagged;
+                let value = self.apply(value)?;
+                match self.handlers.get(&tag) {
+                    Some(handler) => handler(value)
+                        .map_err(|message| TagError { tag, message })?,
+                    None => Value::tagged(tag, value),
+                }
+            },
+        })
+    }
+
+    /// Parse and apply handlers
+    pub fn parse(&self, src: &str) -> Result<Value, TagParseError> {
+        Ok(self.apply(parser::value(src)?)?)
+    }
+}
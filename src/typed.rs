@@ -0,0 +1,311 @@
+use std::borrow::Cow;
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::{parser::{Key, Node}, Value};
+
+pub type TypedTable = LinkedHashMap<String, TypedValue>;
+
+/// Value node with typed scalars, parse by [`parser::typed_value`]
+///
+/// Typing rules of unquoted simple literals:
+///
+/// - `null` and `~` are [`Null`]
+/// - `true` and `false` are [`Bool`]
+/// - `[+-]?[0-9]+` in the range of [`i64`] is [`Int`]
+/// - `[+-]?[0-9]*(.[0-9]*)?([eE][+-]?[0-9]+)?` with digits,
+///   and with `.` or exponent is [`Float`]
+/// - others are [`String`]
+///
+/// Quoted strings (`'...'` or `"..."`) are always [`String`].
+///
+/// Tags are not typing rules and are dropped, e.g `!str 1` is [`Int`],
+/// parse by [`parser::value`] to keep them.
+///
+/// [`parser::typed_value`]: crate::parser::typed_value
+/// [`parser::value`]: crate::parser::value
+/// [`Null`]: TypedValue::Null
+/// [`Bool`]: TypedValue::Bool
+/// [`Int`]: TypedValue::Int
+/// [`Float`]: TypedValue::Float
+/// [`String`]: TypedValue::String
+#[cfg_attr(feature = "serde_impl", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Self>),
+    Table(TypedTable),
+}
+impl TypedValue {
+    /// Apply the typing rules to unquoted simple literal
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::TypedValue;
+    ///
+    /// assert_eq!(TypedValue::from_plain("12"), TypedValue::Int(12));
+    /// assert_eq!(TypedValue::from_plain("-1e3"), TypedValue::Float(-1e3));
+    /// assert_eq!(TypedValue::from_plain("1a"), TypedValue::String("1a".into()));
+    /// ```
+    pub fn from_plain(s: &str) -> Self {
+        match s {
+            "null" | "~" => return Self::Null,
+            "true" => return Self::Bool(true),
+            "false" => return Self::Bool(false),
+            _ => (),
+        }
+        let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+        if !digits.is_empty() && digits.bytes().all(|ch| ch.is_ascii_digit()) {
+            if let Ok(n) = s.parse() {
+                return Self::Int(n);
+            }
+        }
+        if is_float(digits) {
+            if let Ok(n) = s.parse() {
+                return Self::Float(n);
+            }
+        }
+        Self::String(s.into())
+    }
+
+    /// Returns `true` if the value is [`Null`].
+    ///
+    /// [`Null`]: TypedValue::Null
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Self::Bool(v) = self {
+            Some(*v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        if let Self::Int(v) = self {
+            Some(*v)
+        } else {
+            None
+        }
+    }
+
+    /// Get float, or convert from [`Int`]
+    ///
+    /// [`Int`]: TypedValue::Int
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(v) => Some(*v),
+            Self::Int(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Self>> {
+        if let Self::Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&TypedTable> {
+        if let Self::Table(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+}
+
+/// Digits with `.` or exponent, sign is stripped
+fn is_float(s: &str) -> bool {
+    let (mantissa, exp) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i+1..])),
+        None => (s, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (mantissa, None),
+    };
+    let all_digits = |s: &str| s.bytes().all(|ch| ch.is_ascii_digit());
+    let exp_ok = exp.is_none_or(|exp| {
+        let exp = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+        !exp.is_empty() && all_digits(exp)
+    });
+    (frac.is_some() || exp.is_some())
+        && int.len() + frac.map_or(0, str::len) != 0
+        && all_digits(int)
+        && frac.is_none_or(all_digits)
+        && exp_ok
+}
+
+impl<'a> Node<'a> for TypedValue {
+    fn plain(s: &'a str) -> Self {
+        Self::from_plain(s)
+    }
+    fn quoted(s: Cow<'a, str>) -> Self {
+        Self::String(s.into_owned())
+    }
+    fn array(arr: Vec<Self>) -> Self {
+        Self::Array(arr)
+    }
+    fn table(entries: Vec<(Key<'a>, Self)>) -> Self {
+        let mut table = TypedTable::with_capacity(entries.len());
+        for (k, v) in entries {
+            table.entry(k.name.into_owned()).or_insert(v);
+        }
+        Self::Table(table)
+    }
+    fn tagged(_tag: &'a str, value: Self) -> Self {
+        value
+    }
+    fn null() -> Self {
+        Self::Null
+    }
+}
+
+impl From<Vec<Self>> for TypedValue {
+    fn from(value: Vec<Self>) -> Self {
+        Self::Array(value)
+    }
+}
+impl<const N: usize> From<[Self; N]> for TypedValue {
+    fn from(value: [Self; N]) -> Self {
+        Self::Array(value.into())
+    }
+}
+impl From<TypedTable> for TypedValue {
+    fn from(value: TypedTable) -> Self {
+        Self::Table(value)
+    }
+}
+/// Apply the typing rules to all strings, tags are ignored
+impl From<Value> for TypedValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::String(s) => Self::from_plain(&s),
+            Value::Array(arr) => {
+                Self::Array(arr.into_iter().map(Into::into).collect())
+            },
+            Value::Table(table) => {
+                Self::Table(table.into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect())
+            },
+            Value::Tagged(tagged) => tagged.value.into(),
+            Value::Null => Self::Null,
+        }
+    }
+}
+/// Convert scalars to string, e.g `Int(2)` to `"2"`, and `Null` to [`Value::Null`]
+///
+/// Floats always keep `.` or exponent, e.g `Float(1.0)` to `"1.0"`
+impl From<TypedValue> for Value {
+    fn from(value: TypedValue) -> Self {
+        match value {
+            TypedValue::Null => Self::Null,
+            TypedValue::Bool(b) => b.to_string().into(),
+            TypedValue::Int(n) => n.to_string().into(),
+            TypedValue::Float(n) => format!("{n:?}").into(),
+            TypedValue::String(s) => s.into(),
+            TypedValue::Array(arr) => {
+                Self::Array(arr.into_iter().map(Into::into).collect())
+            },
+            TypedValue::Table(table) => {
+                Self::Table(table.into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn from_plain_test() {
+        use TypedValue as TV;
+        let tests = [
+            ("null", TV::Null),
+            ("~", TV::Null),
+            ("true", TV::Bool(true)),
+            ("false", TV::Bool(false)),
+            ("True", TV::String("True".into())),
+            ("0", TV::Int(0)),
+            ("-12", TV::Int(-12)),
+            ("+12", TV::Int(12)),
+            ("007", TV::Int(7)),
+            ("9223372036854775807", TV::Int(i64::MAX)),
+            ("9223372036854775808", TV::String("9223372036854775808".into())),
+            ("1.5", TV::Float(1.5)),
+            ("-.5", TV::Float(-0.5)),
+            ("5.", TV::Float(5.0)),
+            ("1e3", TV::Float(1e3)),
+            ("1E-3", TV::Float(1e-3)),
+            (".", TV::String(".".into())),
+            ("e3", TV::String("e3".into())),
+            ("1e", TV::String("1e".into())),
+            ("inf", TV::String("inf".into())),
+            ("NaN", TV::String("NaN".into())),
+            ("0x10", TV::String("0x10".into())),
+            ("-", TV::String("-".into())),
+        ];
+        for (src, expect) in tests {
+            assert_eq!(TV::from_plain(src), expect, "{src:?}");
+        }
+    }
+
+    #[test]
+    fn typed_value_test() {
+        use TypedValue as TV;
+        let tests = [
+            ("1", TV::Int(1)),
+            ("'1'", TV::String("1".into())),
+            ("\"true\"", TV::String("true".into())),
+            ("'null'", TV::String("null".into())),
+            ("!str 1", TV::Int(1)),
+            ("a: 1\nb: '2'\nc:\n- ~", TV::Table(TypedTable::from_iter([
+                ("a".into(), TV::Int(1)),
+                ("b".into(), TV::String("2".into())),
+                ("c".into(), [TV::Null].into()),
+            ]))),
+        ];
+        for (src, expect) in tests {
+            assert_eq!(parser::typed_value(src), Ok(expect), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn float_round_trip_test() {
+        use TypedValue as TV;
+        for n in [1.0, -0.0, 1.5, 1e300, 1e-300, f64::MAX] {
+            let value = Value::from(TV::Float(n));
+            assert_eq!(TV::from(value.clone()), TV::Float(n), "{value:?}");
+        }
+        assert_eq!(Value::from(TV::Float(1.0)), "1.0".into());
+    }
+
+    #[test]
+    fn default_value_unchanged_test() {
+        let value = parser::value("[1, '1', true, null]").unwrap();
+        assert_eq!(value, ["1", "1", "true", "null"].into());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{Path, Value};
+
+/// Depth-first mutable visitor of [`Value`], used by [`Value::visit_mut`]
+///
+/// # Examples
+/// Redact secrets
+/// ```
+/// use syml::{Path, Segment, Value, VisitMut, parser};
+///
+/// struct Redact;
+/// impl VisitMut for Redact {
+///     fn visit_string(&mut self, path: &Path, value: &mut String) {
+///         if let Some(Segment::Key(key)) = path.segments().last() {
+///             if key.ends_with("password") {
+///                 *value = "***".into();
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut value = parser::value("db: [{user: a, password: b}]").unwrap();
+/// value.visit_mut(&mut Redact);
+/// assert_eq!(value, parser::value("db: [{user: a, password: '***'}]").unwrap());
+/// ```
+pub trait VisitMut {
+    /// Called on every node, tags are not skipped
+    ///
+    /// The default calls [`visit_string`] on string,
+    /// or [`visit_children`] on others.
+    ///
+    /// [`visit_string`]: VisitMut::visit_string
+    fn visit_value(&mut self, path: &Path, value: &mut Value) {
+        match value.untagged_mut() {
+            Value::String(s) => self.visit_string(path, s),
+            _ => visit_children(self, path, value),
+        }
+    }
+
+    /// Called on every string by the default [`visit_value`]
+    ///
+    /// [`visit_value`]: VisitMut::visit_value
+    fn visit_string(&mut self, path: &Path, value: &mut String) {
+        let _ = (path, value);
+    }
+}
+
+/// Call [`VisitMut::visit_value`] on items of array or entries of table,
+/// tags are skipped
+pub fn visit_children<V: VisitMut + ?Sized>(visitor: &mut V, path: &Path, value: &mut Value) {
+    match value.untagged_mut() {
+        Value::Array(arr) => {
+            for (i, item) in arr.iter_mut().enumerate() {
+                visitor.visit_value(&path.join(i), item);
+            }
+        },
+        Value::Table(table) => {
+            for (k, v) in table.iter_mut() {
+                visitor.visit_value(&path.join(k.as_str()), v);
+            }
+        },
+        _ => (),
+    }
+}
+
+fn walk<'a>(value: &'a Value, path: &mut Path, f: &mut impl FnMut(&Path, &'a Value)) {
+    f(path, value);
+    match value.untagged() {
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                path.push(i);
+                walk(item, path, f);
+                path.pop();
+            }
+        },
+        Value::Table(table) => {
+            for (k, v) in table {
+                path.push(k.as_str());
+                walk(v, path, f);
+                path.pop();
+            }
+        },
+        _ => (),
+    }
+}
+
+fn walk_mut(value: &mut Value, path: &mut Path, f: &mut impl FnMut(&Path, &mut Value)) {
+    f(path, value);
+    match value.untagged_mut() {
+        Value::Array(arr) => {
+            for (i, item) in arr.iter_mut().enumerate() {
+                path.push(i);
+                walk_mut(item, path, f);
+                path.pop();
+            }
+        },
+        Value::Table(table) => {
+            for (k, v) in table.iter_mut() {
+                path.push(k.as_str());
+                walk_mut(v, path, f);
+                path.pop();
+            }
+        },
+        _ => (),
+    }
+}
+
+/// Iterator of string scalars, created by [`Value::leaves`]
+#[derive(Debug, Clone)]
+pub struct Leaves<'a> {
+    stack: Vec<(Path, &'a Value)>,
+}
+impl<'a> Iterator for Leaves<'a> {
+    type Item = (Path, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, value)) = self.stack.pop() {
+            match value.untagged() {
+                Value::String(s) => return Some((path, s)),
+                Value::Array(arr) => {
+                    self.stack.extend(arr.iter().enumerate().rev()
+                        .map(|(i, item)| (path.join(i), item)));
+                },
+                Value::Table(table) => {
+                    self.stack.extend(table.iter().rev()
+                        .map(|(k, v)| (path.join(k.as_str()), v)));
+                },
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+impl Value {
+    /// Call `f` on every node with its path in depth-first order,
+    /// parents before children, tags are skipped when visiting children
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::parser;
+    ///
+    /// let value = parser::value("a: [x, {b: y}]").unwrap();
+    /// let mut paths = vec![];
+    /// value.walk(|path, _| paths.push(path.to_string()));
+    /// assert_eq!(paths, ["", "a", "a[0]", "a[1]", "a[1].b"]);
+    /// ```
+    pub fn walk<'a>(&'a self, mut f: impl FnMut(&Path, &'a Value)) {
+        walk(self, &mut Path::new(), &mut f)
+    }
+
+    /// Same as [`walk`], children are visited after `f` changed the parent
+    ///
+    /// [`walk`]: Value::walk
+    pub fn walk_mut(&mut self, mut f: impl FnMut(&Path, &mut Value)) {
+        walk_mut(self, &mut Path::new(), &mut f)
+    }
+
+    /// Visit the value by [`VisitMut::visit_value`] with root path
+    pub fn visit_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_value(&Path::new(), self)
+    }
+
+    /// Iterate all string scalars with their path in depth-first order
+    ///
+    /// # Examples
+    /// ```
+    /// use syml::parser;
+    ///
+    /// let value = parser::value("a: [x, !t {b: y}, ~]\nc: z").unwrap();
+    /// let leaves: Vec<_> = value.leaves()
+    ///     .map(|(path, s)| format!("{path}={s}"))
+    ///     .collect();
+    /// assert_eq!(leaves, ["a[0]=x", "a[1].b=y", "c=z"]);
+    /// ```
+    pub fn leaves(&self) -> Leaves<'_> {
+        Leaves { stack: vec![(Path::new(), self)] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+    use super::*;
+
+    #[test]
+    fn walk_test() {
+        let value = parser::value("a: !t [x, {b: ~}]\nc: {}").unwrap();
+        let mut nodes = vec![];
+        value.walk(|path, node| nodes.push((path.to_string(), node)));
+        assert_eq!(nodes, [
+            ("".into(), &value),
+            ("a".into(), &value["a"]),
+            ("a[0]".into(), &value["a"][0]),
+            ("a[1]".into(), &value["a"][1]),
+            ("a[1].b".into(), &Value::Null),
+            ("c".into(), &value["c"]),
+        ]);
+
+        let mut value = parser::value("a: [1, 2]\nb: x").unwrap();
+        value.walk_mut(|path, node| {
+            if path.is_empty() {
+                node["b"] = Value::from(["y", "z"]);
+            } else if let Value::String(s) = node {
+                s.insert_str(0, &path.to_pointer());
+            }
+        });
+        assert_eq!(value, parser::value("a: [/a/01, /a/12]\nb: [/b/0y, /b/1z]").unwrap());
+    }
+
+    #[test]
+    fn visit_mut_test() {
+        /// Interpolate `${path}` by strings of the original value, skip `!raw`
+        struct Interpolate(Value);
+        impl VisitMut for Interpolate {
+            fn visit_value(&mut self, path: &Path, value: &mut Value) {
+                if value.tag() != Some("raw") {
+                    match value.untagged_mut() {
+                        Value::String(s) => self.visit_string(path, s),
+                        _ => visit_children(self, path, value),
+                    }
+                }
+            }
+
+            fn visit_string(&mut self, _path: &Path, value: &mut String) {
+                if let Some(path) = value.strip_prefix("${").and_then(|s| s.strip_suffix('}')) {
+                    let target = self.0.pointer(&path.parse().unwrap());
+                    *value = target.and_then(|v| v.untagged().as_str()).unwrap_or_default().to_owned();
+                }
+            }
+        }
+        let mut value = parser::value("\
+host: example.com
+urls: ['${host}', !raw '${host}', {x: '${urls[1]}', y: '${none}'}]
+").unwrap();
+        value.visit_mut(&mut Interpolate(value.clone()));
+        assert_eq!(value, parser::value("\
+host: example.com
+urls: [example.com, !raw '${host}', {x: '${host}', y: ''}]
+").unwrap());
+    }
+
+    #[test]
+    fn leaves_test() {
+        let value = parser::value("[a, [], {b: [c, ~, d]}, !t e]").unwrap();
+        let leaves: Vec<_> = value.leaves()
+            .map(|(path, s)| (path.to_pointer(), s))
+            .collect();
+        assert_eq!(leaves, [("/0".into(), "a"), ("/2/b/0".into(), "c"), ("/2/b/2".into(), "d"), ("/3".into(), "e")]);
+        assert_eq!(Value::from("x").leaves().collect::<Vec<_>>(), [(Path::new(), "x")]);
+    }
+}
@@ -0,0 +1,347 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, parse_quote, spanned::Spanned, Attribute, Data, DeriveInput,
+    ExprPath, Fields, FieldsNamed, FieldsUnnamed, Ident, LitStr, Token,
+};
+
+enum DefaultAttr {
+    Trait,
+    Path(ExprPath),
+}
+
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    default: Option<DefaultAttr>,
+    flatten: bool,
+    skip: bool,
+}
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("syml")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    this.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    this.default = Some(if meta.input.peek(Token![=]) {
+                        let path = meta.value()?.parse::<LitStr>()?;
+                        DefaultAttr::Path(path.parse()?)
+                    } else {
+                        DefaultAttr::Trait
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    this.flatten = true;
+                } else if meta.path.is_ident("skip") {
+                    this.skip = true;
+                } else {
+                    return Err(meta.error("unknown syml attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(this)
+    }
+
+    fn parse_only_rename(attrs: &[Attribute], span: &impl Spanned) -> syn::Result<Self> {
+        let this = Self::parse(attrs)?;
+        if this.default.is_some() || this.flatten || this.skip {
+            return Err(syn::Error::new(span.span(), "only `rename` is supported here"));
+        }
+        Ok(this)
+    }
+
+    fn name(&self, ident: &Ident) -> String {
+        self.rename.clone().unwrap_or_else(|| ident.unraw().to_string())
+    }
+
+    fn default_fn(&self) -> TokenStream {
+        match &self.default {
+            Some(DefaultAttr::Path(path)) => quote! { #path },
+            _ => quote! { ::core::default::Default::default },
+        }
+    }
+}
+
+fn check_unnamed(fields: &FieldsUnnamed) -> syn::Result<()> {
+    for field in &fields.unnamed {
+        if field.attrs.iter().any(|attr| attr.path().is_ident("syml")) {
+            return Err(syn::Error::new(field.span(), "attributes need named fields"));
+        }
+    }
+    Ok(())
+}
+
+fn add_bounds(input: &mut DeriveInput, bound: syn::Path) {
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote! { #bound });
+    }
+}
+
+/// Table of `value` into `ctor { .. }`,
+/// flattened fields take the entries not taken by other fields
+fn named_from(fields: &FieldsNamed, ctor: TokenStream) -> syn::Result<TokenStream> {
+    let fields = fields.named.iter()
+        .map(|field| Ok((field.ident.as_ref().unwrap(), Attrs::parse(&field.attrs)?)))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let keys = fields.iter()
+        .filter(|(_, attrs)| !attrs.skip && !attrs.flatten)
+        .map(|(ident, attrs)| attrs.name(ident))
+        .collect::<Vec<_>>();
+    let mut uses_table = false;
+    let mut uses_rest = false;
+    let inits = fields.iter()
+        .map(|(ident, attrs)| {
+            let key = attrs.name(ident);
+            let default = attrs.default_fn();
+            let init = if attrs.skip {
+                quote! { #default() }
+            } else if attrs.flatten {
+                uses_rest = true;
+                quote! { ::syml::FromSyml::from_syml(&rest)? }
+            } else if attrs.default.is_some() {
+                quote! { ::syml::__private::field_or(table, #key, #default)? }
+            } else {
+                quote! { ::syml::__private::field(table, #key)? }
+            };
+            uses_table |= !attrs.skip;
+            quote! { #ident: #init }
+        })
+        .collect::<Vec<_>>();
+    let table = if uses_table {
+        quote! { let table = ::syml::__private::table(value)?; }
+    } else {
+        quote! { ::syml::__private::table(value)?; }
+    };
+    let rest = uses_rest.then(|| quote! {
+        let rest = ::syml::__private::rest(table, &[#(#keys),*]);
+    });
+    Ok(quote! {{
+        #table
+        #rest
+        ::core::result::Result::Ok(#ctor { #(#inits),* })
+    }})
+}
+
+/// Array of `value` into `ctor(..)`
+fn unnamed_from(fields: &FieldsUnnamed, ctor: TokenStream) -> syn::Result<TokenStream> {
+    check_unnamed(fields)?;
+    let len = fields.unnamed.len();
+    if len == 1 {
+        return Ok(quote! {
+            ::syml::FromSyml::from_syml(value).map(#ctor)
+        });
+    }
+    let indices = 0..len;
+    Ok(quote! {{
+        let arr = ::syml::__private::array(value, #len)?;
+        ::core::result::Result::Ok(#ctor(#(::syml::__private::element(arr, #indices)?),*))
+    }})
+}
+
+/// Table from fields, accessed by `access(ident)`
+fn named_to(
+    fields: &FieldsNamed,
+    access: impl Fn(&Ident) -> TokenStream,
+) -> syn::Result<TokenStream> {
+    let inserts = fields.named.iter()
+        .map(|field| {
+            let attrs = Attrs::parse(&field.attrs)?;
+            let ident = field.ident.as_ref().unwrap();
+            let key = attrs.name(ident);
+            let value = access(ident);
+            Ok(if attrs.skip {
+                quote! {}
+            } else if attrs.flatten {
+                quote! {
+                    ::syml::__private::flatten(&mut table, ::syml::ToSyml::to_syml(#value));
+                }
+            } else {
+                quote! {
+                    table.insert(
+                        ::std::string::String::from(#key),
+                        ::syml::ToSyml::to_syml(#value),
+                    );
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(quote! {{
+        #[allow(unused_mut)]
+        let mut table = ::syml::Table::new();
+        #(#inserts)*
+        ::syml::Value::Table(table)
+    }})
+}
+
+/// Array from bindings, single field is the value itself
+fn unnamed_to(bindings: &[TokenStream]) -> TokenStream {
+    match bindings {
+        [value] => quote! { ::syml::ToSyml::to_syml(#value) },
+        _ => quote! {
+            ::syml::Value::Array(::std::vec![#(::syml::ToSyml::to_syml(#bindings)),*])
+        },
+    }
+}
+
+fn skipped(fields: &FieldsNamed) -> syn::Result<Vec<bool>> {
+    fields.named.iter()
+        .map(|field| Ok(Attrs::parse(&field.attrs)?.skip))
+        .collect()
+}
+
+pub(crate) fn expand_from(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    add_bounds(&mut input, parse_quote! { ::syml::FromSyml });
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => named_from(fields, quote! { Self })?,
+            Fields::Unnamed(fields) => unnamed_from(fields, quote! { Self })?,
+            Fields::Unit => quote! {
+                ::syml::__private::unit(value).map(|()| Self)
+            },
+        },
+        Data::Enum(data) => {
+            let mut names = Vec::new();
+            let mut unit_arms = Vec::new();
+            let mut tag_arms = Vec::new();
+            for variant in &data.variants {
+                let attrs = Attrs::parse_only_rename(&variant.attrs, variant)?;
+                let ident = &variant.ident;
+                let name = attrs.name(ident);
+                let body = match &variant.fields {
+                    Fields::Named(fields) => named_from(fields, quote! { Self::#ident })?,
+                    Fields::Unnamed(fields) => unnamed_from(fields, quote! { Self::#ident })?,
+                    Fields::Unit => {
+                        unit_arms.push(quote! {
+                            #name => ::core::result::Result::Ok(Self::#ident),
+                        });
+                        quote! { ::syml::__private::unit(value).map(|()| Self::#ident) }
+                    },
+                };
+                tag_arms.push(quote! { #name => #body, });
+                names.push(name);
+            }
+            if names.is_empty() {
+                return Err(syn::Error::new(input.ident.span(), "enum without variants"));
+            }
+            quote! {
+                const VARIANTS: &[&::core::primitive::str] = &[#(#names),*];
+                match value {
+                    ::syml::Value::Tagged(tagged) => {
+                        let value = &tagged.value;
+                        match tagged.tag.as_str() {
+                            #(#tag_arms)*
+                            tag => ::syml::__private::variant(tag, VARIANTS),
+                        }
+                    },
+                    ::syml::Value::String(name) => match name.as_str() {
+                        #(#unit_arms)*
+                        name => ::syml::__private::variant(name, VARIANTS),
+                    },
+                    value => ::core::result::Result::Err(
+                        ::syml::FromSymlError::expected("string or tagged value", value)
+                    ),
+                }
+            }
+        },
+        Data::Union(data) => {
+            return Err(syn::Error::new(data.union_token.span, "unions are not supported"));
+        },
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::syml::FromSyml for #ident #ty_generics #where_clause {
+            fn from_syml(
+                value: &::syml::Value,
+            ) -> ::core::result::Result<Self, ::syml::FromSymlError> {
+                #body
+            }
+        }
+    })
+}
+
+pub(crate) fn expand_to(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    add_bounds(&mut input, parse_quote! { ::syml::ToSyml });
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => named_to(fields, |ident| quote! { &self.#ident })?,
+            Fields::Unnamed(fields) => {
+                check_unnamed(fields)?;
+                let bindings = (0..fields.unnamed.len())
+                    .map(|i| {
+                        let i = syn::Index::from(i);
+                        quote! { &self.#i }
+                    })
+                    .collect::<Vec<_>>();
+                unnamed_to(&bindings)
+            },
+            Fields::Unit => quote! { ::syml::Value::Null },
+        },
+        Data::Enum(data) => {
+            let arms = data.variants.iter()
+                .map(|variant| {
+                    let attrs = Attrs::parse_only_rename(&variant.attrs, variant)?;
+                    let ident = &variant.ident;
+                    let name = attrs.name(ident);
+                    Ok(match &variant.fields {
+                        Fields::Named(fields) => {
+                            let bound = fields.named.iter()
+                                .zip(skipped(fields)?)
+                                .filter(|(_, skip)| !skip)
+                                .map(|(field, _)| field.ident.as_ref().unwrap())
+                                .collect::<Vec<_>>();
+                            let value = named_to(fields, |ident| quote! { #ident })?;
+                            quote! {
+                                Self::#ident { #(#bound,)* .. } => {
+                                    ::syml::Value::tagged(#name, #value)
+                                },
+                            }
+                        },
+                        Fields::Unnamed(fields) => {
+                            check_unnamed(fields)?;
+                            let bindings = (0..fields.unnamed.len())
+                                .map(|i| format_ident!("f{i}"))
+                                .collect::<Vec<_>>();
+                            let value = unnamed_to(&bindings.iter()
+                                .map(|b| quote! { #b })
+                                .collect::<Vec<_>>());
+                            quote! {
+                                Self::#ident(#(#bindings),*) => {
+                                    ::syml::Value::tagged(#name, #value)
+                                },
+                            }
+                        },
+                        Fields::Unit => quote! {
+                            Self::#ident => ::syml::Value::String(
+                                ::std::string::String::from(#name)
+                            ),
+                        },
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            if arms.is_empty() {
+                quote! { match *self {} }
+            } else {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        },
+        Data::Union(data) => {
+            return Err(syn::Error::new(data.union_token.span, "unions are not supported"));
+        },
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::syml::ToSyml for #ident #ty_generics #where_clause {
+            fn to_syml(&self) -> ::syml::Value {
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,192 @@
+use std::{env, fs, path::PathBuf};
+
+use peg::{error::ParseError, str::LineCol};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::{parse::{Parse, ParseStream}, Ident, LitStr, Token};
+use syml::{parser, TypedValue, Value};
+
+pub(crate) struct Input {
+    path: LitStr,
+    ty: Option<syn::Path>,
+}
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let ty = if input.parse::<Option<Token![as]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { path, ty })
+    }
+}
+
+pub(crate) fn expand(input: Input) -> syn::Result<TokenStream> {
+    let span = input.path.span();
+    let file = input.path.value();
+    let dir = env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let full_path = PathBuf::from(dir).join(&file);
+    let src = fs::read_to_string(&full_path).map_err(|e| {
+        syn::Error::new(span, format!("couldn't read {}: {e}", full_path.display()))
+    })?;
+    let syntax_error = |e: ParseError<LineCol>| {
+        let loc = e.location;
+        syn::Error::new(span, format!(
+            "{file}:{}:{}: syntax error, expected {}",
+            loc.line, loc.column, e.expected,
+        ))
+    };
+    let value = parser::value(&src).map_err(syntax_error)?;
+    let expr = match &input.ty {
+        Some(ty) => {
+            let typed = parser::typed_value(&src).map_err(syntax_error)?;
+            Typed { span, ty }.expand(&value, &typed, "")?
+        },
+        None => value_tokens(&value),
+    };
+    let full_path = full_path.to_string_lossy();
+    Ok(quote! {{
+        const _: &::core::primitive::str = ::core::include_str!(#full_path);
+        #expr
+    }})
+}
+
+fn value_tokens(value: &Value) -> TokenStream {
+    match value {
+        Value::String(s) => quote! {
+            ::syml::Value::String(::std::string::String::from(#s))
+        },
+        Value::Array(arr) => {
+            let items = arr.iter().map(value_tokens);
+            quote! { ::syml::Value::Array(::std::vec![#(#items),*]) }
+        },
+        Value::Table(table) => {
+            let keys = table.keys();
+            let values = table.values().map(value_tokens);
+            quote! {
+                ::syml::Value::Table(::syml::Table::from_iter([
+                    #((::std::string::String::from(#keys), #values)),*
+                ]))
+            }
+        },
+        Value::Tagged(tagged) => {
+            let tag = &tagged.tag;
+            let value = value_tokens(&tagged.value);
+            quote! { ::syml::Value::tagged(#tag, #value) }
+        },
+        Value::Null => quote! { ::syml::Value::Null },
+    }
+}
+
+struct Typed<'a> {
+    span: Span,
+    ty: &'a syn::Path,
+}
+impl Typed<'_> {
+    fn error(&self, path: &str, message: impl std::fmt::Display) -> syn::Error {
+        let path = if path.is_empty() { "root" } else { path };
+        syn::Error::new(self.span, format!("at {path}: {message}"))
+    }
+
+    fn tag_path(&self, tag: &str, path: &str) -> syn::Result<syn::Path> {
+        syn::parse_str(&tag.replace('.', "::"))
+            .map_err(|_| self.error(path, format_args!("invalid tag path `!{tag}`")))
+    }
+
+    fn field(&self, key: &str, path: &str) -> syn::Result<Ident> {
+        syn::parse_str(key)
+            .or_else(|_| syn::parse_str(&format!("r#{key}")))
+            .map_err(|_| self.error(path, format_args!("`{key}` is not a field name")))
+    }
+
+    fn table(
+        &self,
+        ty: &syn::Path,
+        value: &Value,
+        typed: &TypedValue,
+        path: &str,
+    ) -> syn::Result<TokenStream> {
+        let (Value::Table(table), TypedValue::Table(typed)) = (value, typed) else {
+            return Err(self.error(path, "expected table"));
+        };
+        let fields = table.iter()
+            .map(|(k, v)| {
+                let path = if path.is_empty() { k.clone() } else { format!("{path}.{k}") };
+                let typed = typed.get(k)
+                    .ok_or_else(|| self.error(&path, "missing typed value"))?;
+                let field = self.field(k, &path)?;
+                let value = self.expand(v, typed, &path)?;
+                Ok(quote! { #field: #value })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        Ok(quote! { #ty { #(#fields),* } })
+    }
+
+    fn expand(&self, value: &Value, typed: &TypedValue, path: &str) -> syn::Result<TokenStream> {
+        if path.is_empty() {
+            return self.table(self.ty, value.untagged(), typed, path);
+        }
+        Ok(match (value, typed) {
+            (Value::Tagged(tagged), _) => {
+                let ty = self.tag_path(&tagged.tag, path)?;
+                match tagged.value.untagged() {
+                    Value::Table(_) => self.table(&ty, tagged.value.untagged(), typed, path)?,
+                    Value::Null => quote! { #ty },
+                    _ => {
+                        let value = self.expand(&tagged.value, typed, path)?;
+                        quote! { #ty(#value) }
+                    },
+                }
+            },
+            (_, TypedValue::Table(_)) => {
+                return Err(self.error(path, "nested table needs a `!Type` tag"));
+            },
+            (Value::Array(arr), TypedValue::Array(typed)) => {
+                let items = arr.iter().zip(typed).enumerate()
+                    .map(|(i, (v, typed))| self.expand(v, typed, &format!("{path}[{i}]")))
+                    .collect::<syn::Result<Vec<_>>>()?;
+                quote! { ::std::vec![#(#items),*] }
+            },
+            (_, TypedValue::Null) => quote! { ::core::default::Default::default() },
+            (_, TypedValue::Bool(b)) => quote! { #b },
+            (_, &TypedValue::Int(n)) => {
+                let n = Literal::i64_unsuffixed(n);
+                quote! { #n }
+            },
+            (_, &TypedValue::Float(n)) => {
+                if !n.is_finite() {
+                    return Err(self.error(path, "float out of range"));
+                }
+                let n = Literal::f64_unsuffixed(n);
+                quote! { #n }
+            },
+            (_, TypedValue::String(s)) => quote! { ::core::convert::Into::into(#s) },
+            (_, TypedValue::Array(_)) => unreachable!(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(tokens: &str) -> syn::Result<String> {
+        let input = syn::parse_str::<Input>(tokens)?;
+        expand(input).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn error_test() {
+        let tests = [
+            (r#""tests/data/broken.syml""#, "tests/data/broken.syml:3:"),
+            (r#""tests/data/missing.syml""#, "couldn't read"),
+            (r#""tests/data/config.syml" as Config"#, "at db: nested table needs a `!Type` tag"),
+            (r#""tests/data/keys.syml" as Config"#, "at a-b: `a-b` is not a field name"),
+        ];
+        for (input, message) in tests {
+            let e = expand_str(input).unwrap_err().to_string();
+            assert!(e.contains(message), "{input}: {e}");
+        }
+    }
+}
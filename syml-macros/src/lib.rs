@@ -0,0 +1,80 @@
+//! Procedural macros for [SYML](https://docs.rs/syml)
+//!
+//! The expanded code refers to the `syml` crate, it must be a dependency.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod derive;
+mod include;
+
+/// Parse a SYML file at compile time, and expand to its value
+///
+/// The path is relative to `CARGO_MANIFEST_DIR`,
+/// syntax errors are compile errors with the file, line and column.
+///
+/// `include_syml!("file.syml")` expands to a [`syml::Value`] constructor.
+///
+/// `include_syml!("file.syml" as Config)` expands to a typed initializer,
+/// scalars are typed by the rules of [`syml::TypedValue`]:
+///
+/// - Table is a struct initializer, `Config` for the root,
+///   nested tables need a tag as the struct path, e.g `!Server`
+/// - Keys are field names
+/// - Array is [`vec!`]
+/// - Integer, float and bool are literals,
+///   strings are `&str` literals converted by [`Into`]
+/// - `~` is [`Default::default`]
+/// - Other tagged values call the tag with the value, e.g `!Some 80` is `Some(80)`,
+///   tagged `~` is the tag path only, e.g `!Mode.Fast ~` is `Mode::Fast`
+///
+/// `.` in tags is path separator, e.g `!net.Addr` is `net::Addr`.
+///
+/// [`syml::Value`]: https://docs.rs/syml/latest/syml/enum.Value.html
+/// [`syml::TypedValue`]: https://docs.rs/syml/latest/syml/enum.TypedValue.html
+#[proc_macro]
+pub fn include_syml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as include::Input);
+    include::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `syml::FromSyml`
+///
+/// - Struct with named fields is table, tuple struct is array,
+///   newtype struct is the inner value, unit struct is `~`
+/// - Unit variant is string, e.g `Fast`,
+///   other variants are tagged as struct, e.g `!Limit 5`, `!Server {port: 80}`
+///
+/// Type parameters need `FromSyml`.
+/// Errors have the path of the failed node, e.g `servers[1].port`.
+///
+/// Attributes of named fields:
+///
+/// - `#[syml(rename = "name")]` key of the field, also for variants
+/// - `#[syml(default)]` or `#[syml(default = "path")]` value of the missing field
+/// - `#[syml(flatten)]` convert from the entries not taken by the other fields
+/// - `#[syml(skip)]` not converted, value is the default
+///
+/// Missing [`Option`] fields are [`None`].
+#[proc_macro_derive(FromSyml, attributes(syml))]
+pub fn derive_from_syml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::expand_from(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `syml::ToSyml`, the inverse of [`FromSyml`]
+///
+/// Flattened fields must be table or `~`, skipped fields are not written.
+///
+/// [`FromSyml`]: macro@FromSyml
+#[proc_macro_derive(ToSyml, attributes(syml))]
+pub fn derive_to_syml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::expand_to(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
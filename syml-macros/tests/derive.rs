@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use syml::{parser, FromSyml, Path, ToSyml, Value};
+use syml_macros::{FromSyml, ToSyml};
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+struct Config {
+    name: String,
+    #[syml(rename = "listen")]
+    servers: Vec<Server>,
+    #[syml(default)]
+    debug: bool,
+    #[syml(default = "default_level")]
+    level: Level,
+    comment: Option<String>,
+    #[syml(flatten)]
+    extra: BTreeMap<String, Value>,
+    #[syml(skip)]
+    loaded: bool,
+}
+
+fn default_level() -> Level {
+    Level::Info
+}
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+struct Server {
+    host: Host,
+    r#type: Kind,
+    port: u16,
+}
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+struct Host(String);
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+enum Kind {
+    Plain,
+    #[syml(rename = "tls")]
+    Tls { cert: String },
+    Proxy(String, u16),
+    Weight(u8),
+}
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+enum Level {
+    Info,
+    Debug,
+}
+
+#[derive(Debug, PartialEq, FromSyml, ToSyml)]
+struct Pair<T>(T, T);
+
+fn value(src: &str) -> Value {
+    parser::value(src).unwrap_or_else(|e| panic!("{src:?}: {e}"))
+}
+
+#[test]
+fn derive_test() {
+    let src = "\
+name: app
+listen:
+- host: a
+  type: Plain
+  port: 80
+- host: b
+  type: !tls {cert: x.pem}
+  port: 443
+- host: c
+  type: !Proxy [d, 8080]
+  port: 81
+- host: e
+  type: !Weight 3
+  port: 82
+comment: ~
+x: 1
+";
+    let config = Config::from_syml(&value(src)).unwrap();
+    assert_eq!(config, Config {
+        name: "app".into(),
+        servers: vec![
+            Server { host: Host("a".into()), r#type: Kind::Plain, port: 80 },
+            Server {
+                host: Host("b".into()),
+                r#type: Kind::Tls { cert: "x.pem".into() },
+                port: 443,
+            },
+            Server { host: Host("c".into()), r#type: Kind::Proxy("d".into(), 8080), port: 81 },
+            Server { host: Host("e".into()), r#type: Kind::Weight(3), port: 82 },
+        ],
+        debug: false,
+        level: Level::Info,
+        comment: None,
+        extra: [("x".to_owned(), "1".into())].into(),
+        loaded: false,
+    });
+
+    let mut expected = value(src);
+    expected["debug"] = "false".into();
+    expected["level"] = "Info".into();
+    let expected = ["name", "listen", "debug", "level", "comment", "x"].into_iter()
+        .map(|k| (k.to_owned(), expected[k].clone()))
+        .collect::<Value>();
+    assert_eq!(config.to_syml(), expected);
+
+    let pair = Pair::<u8>::from_syml(&value("[1, 2]")).unwrap();
+    assert_eq!(pair, Pair(1, 2));
+    assert_eq!(pair.to_syml(), value("[1, 2]"));
+}
+
+#[test]
+fn derive_error_test() {
+    let tests = [
+        ("listen: []", "missing field `name`"),
+        ("name: [x]\nlisten: []", "name: expected string, found array"),
+        ("name: a\nlisten: [{host: a, type: Plain, port: x}]",
+            "listen[0].port: invalid u16 `x`: invalid digit found in string"),
+        ("name: a\nlisten: [{host: a, type: Tls, port: 1}]",
+            "listen[0].type: unknown variant `Tls`, expected one of `Plain`, `tls`, `Proxy`, `Weight`"),
+        ("name: a\nlisten: [{host: a, type: !tls {}, port: 1}]",
+            "listen[0].type: missing field `cert`"),
+        ("name: a\nlisten: [{host: a, type: !Proxy [a], port: 1}]",
+            "listen[0].type: expected array of length 2, found length 1"),
+        ("name: a\nlisten: [{host: a, type: !Proxy [a, b], port: 1}]",
+            "listen[0].type[1]: invalid u16 `b`: invalid digit found in string"),
+        ("name: a\nlisten: [{host: a, type: !Plain x, port: 1}]",
+            "listen[0].type: expected null, found string"),
+        ("name: a\nlisten: []\nlevel: [x]", "level: expected string or tagged value, found array"),
+        ("[]", "expected table, found array"),
+    ];
+    for (src, message) in tests {
+        let err = Config::from_syml(&value(src)).unwrap_err();
+        assert_eq!(err.to_string(), message, "{src:?}");
+    }
+    let err = Config::from_syml(&value("name: a\nlisten: [{host: [], type: Plain}]")).unwrap_err();
+    assert_eq!(err.path, "listen[0].host".parse::<Path>().unwrap());
+}
@@ -0,0 +1,55 @@
+use syml::{parser, Value};
+use syml_macros::include_syml;
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Fast,
+}
+
+#[derive(Debug, PartialEq)]
+struct Db {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, PartialEq)]
+struct Config {
+    name: &'static str,
+    version: String,
+    port: u32,
+    ratio: f64,
+    debug: bool,
+    tags: Vec<String>,
+    timeout: Option<u64>,
+    mode: Mode,
+    limit: Option<u8>,
+    r#type: String,
+    db: Db,
+    replicas: Vec<Db>,
+}
+
+#[test]
+fn include_value_test() {
+    let value: Value = include_syml!("tests/data/config.syml");
+    let src = include_str!("data/config.syml");
+    assert_eq!(value, parser::value(src).unwrap());
+}
+
+#[test]
+fn include_typed_test() {
+    let config = include_syml!("tests/data/typed.syml" as Config);
+    assert_eq!(config, Config {
+        name: "demo",
+        version: "1.0".into(),
+        port: 8080,
+        ratio: 0.5,
+        debug: false,
+        tags: vec!["a".into(), "b c".into()],
+        timeout: None,
+        mode: Mode::Fast,
+        limit: Some(10),
+        r#type: "x".into(),
+        db: Db { host: "localhost".into(), port: 5432 },
+        replicas: vec![Db { host: "r1".into(), port: 1 }],
+    });
+}
@@ -16,6 +16,8 @@ fn to_json(value: syml::Value) -> json::JsonValue {
                 .map(|(k, v)| (k, to_json(v)))
                 .collect())
         },
+        SV::Tagged(tagged) => to_json(tagged.value),
+        SV::Null => JV::Null,
     }
 }
 
@@ -34,8 +36,7 @@ fn main() -> io::Result<()> {
         }))
         .filter(|path| path.as_ref().is_ok_and(|path| {
             path.file_name()
-                .map(|name| name.to_str())
-                .flatten()
+                .and_then(|name| name.to_str())
                 .is_some_and(|name| name.ends_with(".syml"))
         }))
         .collect::<Result<Vec<_>, _>>()?;
@@ -63,8 +64,8 @@ fn main() -> io::Result<()> {
                 color(91);
                 eprintln!(" fail");
                 color(0);
-                eprintln!("syml: {}", value.to_string());
-                eprintln!("json: {}", json_obj.to_string());
+                eprintln!("syml: {value}");
+                eprintln!("json: {json_obj}");
             },
             Err(e) => {
                 color(91);